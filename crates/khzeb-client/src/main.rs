@@ -58,6 +58,7 @@ pub fn main() {
                     ..
                 } => control_flow.exit(),
                 WindowEvent::RedrawRequested => {
+                    renderer.update_assets();
                     renderer.render();
                     window.request_redraw();
                 }
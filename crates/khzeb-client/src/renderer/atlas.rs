@@ -1,11 +1,13 @@
 use std::ops::Deref;
 
 use bytemuck::{Pod, Zeroable};
+use serde::Deserialize;
 use wgpu::{BindingType, Buffer, BufferBindingType, BufferUsages, Device, Queue};
 
 use super::buffer::{create_buffer, BufferHandle};
 
-#[derive(Clone, Copy, Zeroable, Pod)]
+// Also the format of `.atlas` descriptor files
+#[derive(Clone, Copy, Zeroable, Pod, Deserialize)]
 #[repr(C)]
 pub struct TextureAtlasProperties {
     pub width: u16,
@@ -1,6 +1,6 @@
 use std::sync::Mutex;
 
-use bitflags::{bitflags, Flags};
+use bitflags::bitflags;
 use bytemuck::{Pod, Zeroable};
 use glam::{IVec2, Vec2};
 use lazy_static::lazy_static;
@@ -61,6 +61,7 @@ impl BatchMetadata {
         Self::default()
     }
 
+    #[allow(dead_code)]
     pub fn with_snap_to_grid(self) -> Self {
         Self {
             flags: self.flags | BatchMetadataFlags::SNAP_INSTANCES_TO_GRID,
@@ -72,6 +73,7 @@ impl BatchMetadata {
         Self { origin, ..self }
     }
 
+    #[allow(dead_code)]
     pub fn with_scale(self, scale: f32) -> Self {
         Self { scale, ..self }
     }
@@ -181,7 +183,7 @@ impl Batch {
         mutable.instance_dirty_flag.clear()
     }
 
-    pub fn buffer_slice(&self) -> BufferSlice {
+    pub fn buffer_slice(&self) -> BufferSlice<'_> {
         let bytes_size = self.size() * size_of::<BatchInstance>() as u64;
         self.instance_buffer.slice(..bytes_size)
     }
@@ -191,6 +193,7 @@ impl Batch {
         mutable.size as u64
     }
 
+    #[allow(dead_code)]
     pub fn mutate_metadata(&self, mut mutator: impl FnMut(&mut BatchMetadata)) {
         let mut mutable = self.mutable.lock().unwrap();
         let metadata_before_mutator = mutable.metadata;
@@ -254,12 +257,14 @@ impl BatchInstance {
         Self::default()
     }
 
+    #[allow(dead_code)]
     pub fn new_i32(position: IVec2, scale: f32) -> Self {
         Self::default()
             .with_position_i32(position)
             .with_scale(scale)
     }
 
+    #[allow(dead_code)]
     pub fn new_f32(position: Vec2, scale: f32) -> Self {
         Self::default()
             .with_position_f32(position)
@@ -270,6 +275,7 @@ impl BatchInstance {
         Self { tint, ..self }
     }
 
+    #[allow(dead_code)]
     pub fn with_scale(self, scale: f32) -> Self {
         Self { scale, ..self }
     }
@@ -288,6 +294,7 @@ impl BatchInstance {
         }
     }
 
+    #[allow(dead_code)]
     pub fn with_position_i32(self, int: IVec2) -> Self {
         Self {
             position: InstancePosition { int },
@@ -6,7 +6,6 @@ use wgpu::{
 };
 
 pub struct BindingLayout {
-    entries: Vec<BindGroupLayoutEntry>,
     pub layout: BindGroupLayout,
 }
 
@@ -57,7 +56,7 @@ pub fn create_binding_layout(
         entries: &entries,
     });
 
-    BindingLayout { entries, layout }
+    BindingLayout { layout }
 }
 
 pub fn create_binding<'resource>(
@@ -30,7 +30,6 @@ impl Camera {
         let bottom = self.position.y - half_height;
         let top = self.position.y + half_height;
 
-        let ortho = Mat4::orthographic_rh_gl(left, right, bottom, top, -1.0, 1.0);
-        ortho
+        Mat4::orthographic_rh_gl(left, right, bottom, top, -1.0, 1.0)
     }
 }
@@ -26,8 +26,8 @@ impl Rgba {
     }
 }
 
-impl Into<u32> for Rgba {
-    fn into(self) -> u32 {
-        (self.r as u32) << 24 | (self.g as u32) << 16 | (self.b as u32) << 8 | (self.a as u32)
+impl From<Rgba> for u32 {
+    fn from(value: Rgba) -> Self {
+        (value.r as u32) << 24 | (value.g as u32) << 16 | (value.b as u32) << 8 | (value.a as u32)
     }
 }
@@ -8,17 +8,18 @@ pub mod dirty;
 pub mod pipeline;
 pub mod texture;
 
-use std::sync::Arc;
+use std::{env, path::PathBuf, sync::Arc};
 
-use atlas::TextureAtlas;
+use atlas::{TextureAtlas, TextureAtlasProperties};
 use batch::{Batch, BatchInstance, BatchMetadata};
-use bindings::{create_binding, create_binding_layout, Binding};
+use bindings::{create_binding, create_binding_layout, Binding, BindingLayout};
 use buffer::{create_buffer, BufferHandle};
 use bytemuck::{Pod, Zeroable};
 use camera::Camera;
+use image::DynamicImage;
 use pipeline::{create_render_pipeline, Pipeline};
 use pollster::FutureExt;
-use texture::Texture;
+use texture::{ImageLoader, Texture};
 use wgpu::{
     AddressMode, Backends, BindingResource, BindingType, BufferBindingType, BufferUsages, Device,
     DeviceDescriptor, Features, FilterMode, Instance, InstanceDescriptor, Limits, PowerPreference,
@@ -27,7 +28,13 @@ use wgpu::{
 };
 use winit::{dpi::PhysicalSize, window::Window};
 
-use khzeb::prelude::*;
+use khzeb::{
+    asset::{
+        loader::JsonLoader,
+        server::{AssetEvent, AssetServer},
+    },
+    prelude::*,
+};
 
 const SHADER_CONTEXT_BIND_GROUP_INDEX: u32 = 0;
 const TEXTURE_BIND_GROUP_INDEX: u32 = 1;
@@ -39,6 +46,7 @@ pub struct Renderer<'surface, 'window: 'surface> {
     queue: Queue,
     config: SurfaceConfiguration,
     size: PhysicalSize<u32>,
+    #[allow(dead_code)]
     window: &'window Window,
 
     universal_sampler: Sampler,
@@ -49,11 +57,15 @@ pub struct Renderer<'surface, 'window: 'surface> {
     batches: Vec<Arc<Batch>>,
 
     texture_registry: Registry,
+
+    assets: AssetServer,
+    world00_image: Handle<DynamicImage>,
 }
 
 struct LookupTable {
     shader_context_buffer: BufferHandle<ShaderContext>,
     shader_context_bind_group: Binding,
+    texture_binding_layout: BindingLayout,
     texture_bind_group: Binding,
     texture_atlas: TextureAtlas,
 
@@ -66,6 +78,61 @@ pub struct ShaderContext {
     view_projection: [f32; 16],
 }
 
+// The loaders for the assets the renderer consumes
+pub fn add_asset_loaders(server: &mut AssetServer) {
+    server
+        .add_loader(ImageLoader)
+        .add_loader(JsonLoader::<TextureAtlasProperties>::new(&["atlas"]));
+}
+
+// Given by `KHZEB_ASSETS`, the assets of the source tree otherwise
+fn asset_root() -> PathBuf {
+    env::var_os("KHZEB_ASSETS")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| concat!(env!("CARGO_MANIFEST_DIR"), "/assets").into())
+}
+
+// Falls back to the copy baked into the binary when the file can't be loaded
+fn load_image(
+    assets: &mut AssetServer,
+    name: &str,
+    embedded: &[u8],
+) -> (Handle<DynamicImage>, DynamicImage) {
+    match assets.load::<DynamicImage>(name) {
+        Ok(handle) => {
+            assets.wait();
+            if let Some(image) = assets.get(&handle).cloned() {
+                return (handle, image);
+            }
+            log::warn!("Failed to load {name}, using the embedded copy");
+        }
+        Err(err) => log::warn!("Failed to load {name}, using the embedded copy: {err}"),
+    }
+
+    let image = image::load_from_memory(embedded).expect("Embedded images are valid");
+    (assets.add(name, image.clone()), image)
+}
+
+fn create_texture_binding(
+    device: &Device,
+    layout: &BindingLayout,
+    atlas: &TextureAtlas,
+    sampler: &Sampler,
+    texture: &Texture,
+) -> Binding {
+    let view = texture.to_view();
+
+    create_binding(
+        device,
+        layout,
+        [
+            atlas.as_entire_binding(),
+            BindingResource::Sampler(sampler),
+            BindingResource::TextureView(&view),
+        ],
+    )
+}
+
 impl<'surface, 'window> Renderer<'surface, 'window> {
     pub fn new(window: &'window Window) -> Self {
         let size = window.inner_size();
@@ -132,16 +199,21 @@ impl<'surface, 'window> Renderer<'surface, 'window> {
             ..Default::default()
         });
 
-        let world00_texture_raw = include_bytes!("./textures/world00.png");
-        let world00_texture_image = image::load_from_memory(world00_texture_raw).unwrap();
+        let mut assets = AssetServer::new(asset_root());
+        add_asset_loaders(&mut assets);
+        if let Err(err) = assets.watch() {
+            log::warn!("Assets won't be hot-reloaded: {err}");
+        }
 
-        let world00_texture = Texture::new(
-            &device,
-            &queue,
-            world00_texture_image,
-            TextureUsages::TEXTURE_BINDING,
+        let (world00_image, world00) = load_image(
+            &mut assets,
+            "textures/world00.png",
+            include_bytes!("../../assets/textures/world00.png"),
         );
 
+        let world00_texture =
+            Texture::new(&device, &queue, world00, TextureUsages::TEXTURE_BINDING);
+
         let shader_ctx_binding_layout = create_binding_layout(
             &device,
             ShaderStages::VERTEX,
@@ -155,8 +227,6 @@ impl<'surface, 'window> Renderer<'surface, 'window> {
         let shader_context_buffer =
             create_buffer::<ShaderContext>(&device, BufferUsages::UNIFORM | BufferUsages::COPY_DST);
 
-        let world00_view = world00_texture.to_view();
-
         let shader_context_bind_group = create_binding(
             &device,
             &shader_ctx_binding_layout,
@@ -179,14 +249,12 @@ impl<'surface, 'window> Renderer<'surface, 'window> {
             ],
         );
 
-        let texture_bind_group = create_binding(
+        let texture_bind_group = create_texture_binding(
             &device,
             &texture_binding_layout,
-            [
-                texture_atlas.as_entire_binding(),
-                BindingResource::Sampler(&universal_sampler),
-                BindingResource::TextureView(&world00_view),
-            ],
+            &texture_atlas,
+            &universal_sampler,
+            &world00_texture,
         );
 
         let batch_shader = device.create_shader_module(wgpu::include_wgsl!("shaders/batch.wgsl"));
@@ -208,6 +276,7 @@ impl<'surface, 'window> Renderer<'surface, 'window> {
         let lookup = LookupTable {
             shader_context_buffer,
             shader_context_bind_group,
+            texture_binding_layout,
             texture_bind_group,
             batch_pipeline,
             texture_atlas,
@@ -234,9 +303,49 @@ impl<'surface, 'window> Renderer<'surface, 'window> {
             camera,
             lookup,
             texture_registry,
+
+            assets,
+            world00_image,
+        }
+    }
+
+    // Picks up finished loads and rebuilds what uses the assets that changed on disk
+    pub fn update_assets(&mut self) {
+        for event in self.assets.update() {
+            match event {
+                AssetEvent::Modified(name) if name == *self.world00_image.name() => {
+                    self.reload_world00();
+                }
+                AssetEvent::ReloadFailed(name, err) => {
+                    log::warn!("Failed to reload {}: {err}", name.as_str());
+                }
+                _ => {}
+            }
         }
     }
 
+    fn reload_world00(&mut self) {
+        let Some(image) = self.assets.get(&self.world00_image) else {
+            return;
+        };
+
+        let texture = Texture::new(
+            &self.device,
+            &self.queue,
+            image.clone(),
+            TextureUsages::TEXTURE_BINDING,
+        );
+
+        self.lookup.texture_bind_group = create_texture_binding(
+            &self.device,
+            &self.lookup.texture_binding_layout,
+            &self.lookup.texture_atlas,
+            &self.universal_sampler,
+            &texture,
+        );
+        self.texture_registry.put("textures/world00", texture);
+    }
+
     pub fn resize(&mut self, new_size: PhysicalSize<u32>) {
         if new_size.width > 0 && new_size.height > 0 {
             self.size = new_size;
@@ -1,6 +1,10 @@
 use std::ops::Deref;
 
 use image::DynamicImage;
+use khzeb::{
+    asset::loader::{AssetError, AssetLoader},
+    utils::Name,
+};
 use wgpu::{
     Device, Origin3d, Queue, TexelCopyBufferLayout, TexelCopyTextureInfo, Texture as WGPUTexture,
     TextureAspect, TextureUsages, TextureView as WGPUTextureView, TextureViewDescriptor,
@@ -72,3 +76,18 @@ impl Texture {
         TextureView { view }
     }
 }
+
+// Decodes PNG and JPEG files, turned into a `Texture` once there's a device
+pub struct ImageLoader;
+
+impl AssetLoader for ImageLoader {
+    type Asset = DynamicImage;
+
+    fn extensions(&self) -> &[&str] {
+        &["png", "jpg", "jpeg"]
+    }
+
+    fn load(&self, bytes: &[u8], _: &Name) -> Result<DynamicImage, AssetError> {
+        image::load_from_memory(bytes).map_err(|err| AssetError::Format(err.to_string()))
+    }
+}
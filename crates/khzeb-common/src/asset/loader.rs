@@ -0,0 +1,136 @@
+use std::{
+    any::{type_name, Any, TypeId},
+    error::Error,
+    fmt, io,
+    marker::PhantomData,
+};
+
+use serde::de::DeserializeOwned;
+
+use crate::{utils::Name, world::prefab::Prefabs};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AssetError {
+    Io(String),
+    // No loader is registered for the extension
+    NoLoader(Name),
+    // The loader for the extension produces a different type
+    TypeMismatch {
+        name: Name,
+        expected: &'static str,
+        loaded: &'static str,
+    },
+    // The file could be read, but not turned into an asset
+    Format(String),
+    Watch(String),
+    // The loader panicked, with the message it panicked with
+    Panic(String),
+}
+
+impl fmt::Display for AssetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AssetError::Io(reason) => write!(f, "failed to read asset: {reason}"),
+            AssetError::NoLoader(name) => write!(f, "no loader for asset {}", &**name),
+            AssetError::TypeMismatch {
+                name,
+                expected,
+                loaded,
+            } => write!(f, "asset {} is loaded as {loaded}, not {expected}", &**name),
+            AssetError::Format(reason) => write!(f, "malformed asset: {reason}"),
+            AssetError::Watch(reason) => write!(f, "failed to watch assets: {reason}"),
+            AssetError::Panic(message) => write!(f, "asset loader panicked: {message}"),
+        }
+    }
+}
+
+impl Error for AssetError {}
+
+impl From<io::Error> for AssetError {
+    fn from(value: io::Error) -> Self {
+        AssetError::Io(value.to_string())
+    }
+}
+
+impl From<serde_json::Error> for AssetError {
+    fn from(value: serde_json::Error) -> Self {
+        AssetError::Format(value.to_string())
+    }
+}
+
+// Turns the bytes of a file into an asset, picked by the extension of the file
+pub trait AssetLoader: Send + Sync + 'static {
+    type Asset: Send + Sync + 'static;
+
+    // Without the dot, like `png`
+    fn extensions(&self) -> &[&str];
+
+    fn load(&self, bytes: &[u8], name: &Name) -> Result<Self::Asset, AssetError>;
+}
+
+pub(crate) type BoxedAsset = Box<dyn Any + Send + Sync>;
+
+// Object safe side of `AssetLoader`, so loaders of different assets share a map
+pub(crate) trait ErasedLoader: Send + Sync {
+    fn asset_type_id(&self) -> TypeId;
+
+    fn asset_type_name(&self) -> &'static str;
+
+    fn load_boxed(&self, bytes: &[u8], name: &Name) -> Result<BoxedAsset, AssetError>;
+}
+
+impl<L: AssetLoader> ErasedLoader for L {
+    fn asset_type_id(&self) -> TypeId {
+        TypeId::of::<L::Asset>()
+    }
+
+    fn asset_type_name(&self) -> &'static str {
+        type_name::<L::Asset>()
+    }
+
+    fn load_boxed(&self, bytes: &[u8], name: &Name) -> Result<BoxedAsset, AssetError> {
+        Ok(Box::new(self.load(bytes, name)?))
+    }
+}
+
+// Any type readable from JSON, under extensions of its own like `atlas`
+pub struct JsonLoader<T> {
+    extensions: Vec<&'static str>,
+    marker: PhantomData<fn() -> T>,
+}
+
+impl<T> JsonLoader<T> {
+    pub fn new(extensions: &[&'static str]) -> Self {
+        Self {
+            extensions: extensions.to_vec(),
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<T: DeserializeOwned + Send + Sync + 'static> AssetLoader for JsonLoader<T> {
+    type Asset = T;
+
+    fn extensions(&self) -> &[&str] {
+        &self.extensions
+    }
+
+    fn load(&self, bytes: &[u8], _: &Name) -> Result<T, AssetError> {
+        Ok(serde_json::from_slice(bytes)?)
+    }
+}
+
+pub struct PrefabLoader;
+
+impl AssetLoader for PrefabLoader {
+    type Asset = Prefabs;
+
+    fn extensions(&self) -> &[&str] {
+        &["prefab", "prefabs"]
+    }
+
+    fn load(&self, bytes: &[u8], _: &Name) -> Result<Prefabs, AssetError> {
+        let text = std::str::from_utf8(bytes).map_err(|err| AssetError::Format(err.to_string()))?;
+        Prefabs::from_text(text).map_err(|err| AssetError::Format(err.to_string()))
+    }
+}
@@ -0,0 +1,646 @@
+use std::{
+    any::{type_name, Any, TypeId},
+    collections::HashMap,
+    fs,
+    panic::{self, AssertUnwindSafe},
+    path::{Path, PathBuf},
+    sync::{
+        mpsc::{self, Receiver, Sender},
+        Arc, Mutex,
+    },
+};
+
+use crate::{
+    utils::{Handle, Name, Registry, Resource},
+    world::{event::Events, world::World},
+};
+
+use super::{
+    loader::{AssetError, AssetLoader, BoxedAsset, ErasedLoader},
+    watch::AssetWatcher,
+};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum LoadState {
+    Loading,
+    Loaded,
+    Failed(AssetError),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AssetEvent {
+    Loaded(Name),
+    // Reloaded, the handles now point to the new version
+    Modified(Name),
+    // The last strong handle was dropped and the asset is gone
+    Freed(Name),
+    // Reading the file again failed, the handles keep pointing to the previous version
+    ReloadFailed(Name, AssetError),
+}
+
+struct AssetEntry {
+    state: LoadState,
+    // Of the asset the name was first requested as
+    type_id: TypeId,
+    type_name: &'static str,
+    // Of the latest load, results of the older ones are dropped
+    generation: u64,
+    // The file changed during the first load
+    reload_queued: bool,
+}
+
+impl AssetEntry {
+    fn new<A: 'static>(state: LoadState) -> Self {
+        Self {
+            state,
+            type_id: TypeId::of::<A>(),
+            type_name: type_name::<A>(),
+            generation: 0,
+            reload_queued: false,
+        }
+    }
+
+    fn check<A: 'static>(&self, name: &Name) -> Result<(), AssetError> {
+        if self.type_id == TypeId::of::<A>() {
+            return Ok(());
+        }
+
+        Err(AssetError::TypeMismatch {
+            name: name.clone(),
+            expected: type_name::<A>(),
+            loaded: self.type_name,
+        })
+    }
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "unknown panic".to_string()
+    }
+}
+
+struct LoadResult {
+    name: Name,
+    generation: u64,
+    result: Result<BoxedAsset, AssetError>,
+}
+
+// Loads files under the root directory on background threads.
+// Assets are named by their path relative to the root, like `textures/world00.png`,
+// and show up once `update` picks up the finished loads.
+pub struct AssetServer {
+    root: PathBuf,
+    // By extension
+    loaders: HashMap<Name, Arc<dyn ErasedLoader>>,
+    assets: Registry,
+    entries: HashMap<Name, AssetEntry>,
+    sender: Sender<LoadResult>,
+    // Behind a mutex only to make the server shareable as a world resource
+    receiver: Mutex<Receiver<LoadResult>>,
+    pending: usize,
+    // Shared by all assets, so a name that was freed and requested again can't confuse them
+    next_generation: u64,
+    events: Vec<AssetEvent>,
+    watcher: Option<AssetWatcher>,
+}
+
+impl AssetServer {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        Self {
+            root: root.into(),
+            loaders: Default::default(),
+            assets: Registry::new(),
+            entries: Default::default(),
+            sender,
+            receiver: Mutex::new(receiver),
+            pending: 0,
+            next_generation: 0,
+            events: vec![],
+            watcher: None,
+        }
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    // Replaces the loaders previously added for the same extensions
+    pub fn add_loader(&mut self, loader: impl AssetLoader) -> &mut Self {
+        let extensions: Vec<_> = loader.extensions().iter().map(Name::new).collect();
+        let loader: Arc<dyn ErasedLoader> = Arc::new(loader);
+        for extension in extensions {
+            self.loaders.insert(extension, loader.clone());
+        }
+        self
+    }
+
+    // Starts loading the file unless it was requested before, the handle is usable right away.
+    // Loads that failed are tried again. The asset is unloaded once every handle to it is dropped.
+    pub fn load<A: Send + Sync + 'static>(
+        &mut self,
+        path: impl Into<Name>,
+    ) -> Result<Handle<A>, AssetError> {
+        let name = path.into();
+
+        let retry = match self.entries.get(&name) {
+            Some(entry) => {
+                entry.check::<A>(&name)?;
+                matches!(entry.state, LoadState::Failed(_))
+            }
+            None => true,
+        };
+
+        if retry {
+            let loader = self.loader_for::<A>(&name)?;
+            self.entries
+                .entry(name.clone())
+                .or_insert_with(|| AssetEntry::new::<A>(LoadState::Loading))
+                .state = LoadState::Loading;
+            self.spawn_load(name.clone(), loader);
+        }
+
+        Ok(self.assets.handle(name))
+    }
+
+    // Adds an asset that does not come from a file, like one baked into the binary
+    pub fn add<A: Send + Sync + 'static>(&mut self, name: impl Into<Name>, asset: A) -> Handle<A> {
+        let handle = self.assets.put_counted(name, asset);
+        let mut entry = AssetEntry::new::<A>(LoadState::Loaded);
+        // Loads still running for the name are outdated
+        entry.generation = self.next_generation;
+        self.next_generation += 1;
+        self.entries.insert(handle.name().clone(), entry);
+        handle
+    }
+
+    // Reloads loaded assets whenever their files change, picked up by `update`
+    pub fn watch(&mut self) -> Result<(), AssetError> {
+        if self.watcher.is_none() {
+            self.watcher = Some(AssetWatcher::new(&self.root)?);
+        }
+        Ok(())
+    }
+
+    pub fn is_watching(&self) -> bool {
+        self.watcher.is_some()
+    }
+
+    // Reads the file of the asset again, the old version stays until the new one is loaded.
+    // Returns whether the asset was requested and comes from a file.
+    pub fn reload(&mut self, name: &Name) -> bool {
+        if !self.entries.contains_key(name) {
+            return false;
+        }
+
+        // Type checked when it was first loaded
+        let Some(loader) = name
+            .extension()
+            .and_then(|extension| self.loaders.get(&Name::new(extension)))
+        else {
+            return false;
+        };
+
+        self.spawn_load(name.clone(), loader.clone());
+        true
+    }
+
+    fn loader_for<A: 'static>(&self, name: &Name) -> Result<Arc<dyn ErasedLoader>, AssetError> {
+        let loader = name
+            .extension()
+            .and_then(|extension| self.loaders.get(&Name::new(extension)))
+            .ok_or_else(|| AssetError::NoLoader(name.clone()))?;
+
+        if loader.asset_type_id() != TypeId::of::<A>() {
+            return Err(AssetError::TypeMismatch {
+                name: name.clone(),
+                expected: type_name::<A>(),
+                loaded: loader.asset_type_name(),
+            });
+        }
+
+        Ok(loader.clone())
+    }
+
+    // Expects the entry for the name to be there already
+    fn spawn_load(&mut self, name: Name, loader: Arc<dyn ErasedLoader>) {
+        let path = self.root.join(name.as_str());
+        let sender = self.sender.clone();
+        self.pending += 1;
+
+        let generation = self.next_generation;
+        self.next_generation += 1;
+        let entry = self.entries.get_mut(&name).unwrap();
+        entry.generation = generation;
+        entry.reload_queued = false;
+
+        rayon::spawn(move || {
+            // A panic would abort the process and leave `wait` hanging
+            let result = fs::read(&path).map_err(AssetError::from).and_then(|bytes| {
+                panic::catch_unwind(AssertUnwindSafe(|| loader.load_boxed(&bytes, &name)))
+                    .unwrap_or_else(|payload| Err(AssetError::Panic(panic_message(&*payload))))
+            });
+            // The server is gone, nobody is waiting for the asset
+            sender
+                .send(LoadResult {
+                    name,
+                    generation,
+                    result,
+                })
+                .ok();
+        });
+    }
+
+    // Stores the assets that finished loading since the last update and frees the unused ones,
+    // returning what happened since then
+    pub fn update(&mut self) -> Vec<AssetEvent> {
+        let changed = self
+            .watcher
+            .as_mut()
+            .map(AssetWatcher::changed)
+            .unwrap_or_default();
+        for name in changed {
+            self.file_changed(&name);
+        }
+
+        let finished: Vec<_> = self.receiver.get_mut().unwrap().try_iter().collect();
+        for result in finished {
+            self.finish(result);
+        }
+
+        let freed = self.assets.free_unused();
+        self.events.extend(freed.into_iter().map(AssetEvent::Freed));
+        // Including the ones dropped before they finished loading
+        self.entries.retain(|name, _| self.assets.is_counted(name));
+
+        std::mem::take(&mut self.events)
+    }
+
+    fn file_changed(&mut self, name: &Name) {
+        match self.entries.get_mut(name) {
+            // Read once the first load is done, it might have missed the change
+            Some(entry) if entry.state == LoadState::Loading => entry.reload_queued = true,
+            // Files that are not assets, or not in use, are skipped
+            _ => {
+                self.reload(name);
+            }
+        }
+    }
+
+    // Blocks until every requested asset is either loaded or failed
+    pub fn wait(&mut self) {
+        while self.pending > 0 {
+            let result = self.receiver.get_mut().unwrap().recv().unwrap();
+            self.finish(result);
+        }
+    }
+
+    fn finish(
+        &mut self,
+        LoadResult {
+            name,
+            generation,
+            result,
+        }: LoadResult,
+    ) {
+        self.pending -= 1;
+        // Nobody wants it anymore
+        if self.assets.strong_count(&name) == 0 {
+            return;
+        }
+        let Some(entry) = self.entries.get_mut(&name) else {
+            return;
+        };
+        // Superseded by a load started after this one
+        if entry.generation != generation {
+            return;
+        }
+
+        match result {
+            Ok(asset) => {
+                let event = if self.assets.contains(&name) {
+                    AssetEvent::Modified(name.clone())
+                } else {
+                    AssetEvent::Loaded(name.clone())
+                };
+                self.assets.put_boxed(name.clone(), asset);
+                self.events.push(event);
+                entry.state = LoadState::Loaded;
+            }
+            Err(err) if self.assets.contains(&name) => {
+                self.events
+                    .push(AssetEvent::ReloadFailed(name.clone(), err));
+            }
+            Err(err) => entry.state = LoadState::Failed(err),
+        }
+
+        if entry.reload_queued {
+            self.reload(&name);
+        }
+    }
+
+    // `None` for assets that were never requested
+    pub fn load_state<A>(&self, handle: &Resource<A>) -> Option<&LoadState> {
+        self.entries.get(handle.name()).map(|entry| &entry.state)
+    }
+
+    pub fn get<A: 'static>(&self, handle: &Resource<A>) -> Option<&A> {
+        self.assets.get(handle.clone())
+    }
+
+    pub fn get_mut<A: 'static>(&mut self, handle: &Resource<A>) -> Option<&mut A> {
+        self.assets.get_mut(handle.clone())
+    }
+}
+
+impl World {
+    // Updates the `AssetServer` resource and sends what happened as `AssetEvent`s
+    pub fn update_assets(&mut self) {
+        let Ok(mut server) = self.resource_mut::<AssetServer>() else {
+            return;
+        };
+        let events = server.update();
+
+        self.add_event::<AssetEvent>();
+        self.resource_mut::<Events<AssetEvent>>()
+            .unwrap()
+            .send_batch(events);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        asset::loader::PrefabLoader,
+        world::{prefab::Prefabs, transform::Transform},
+    };
+
+    use super::*;
+
+    // Per process, so concurrent test runs don't share files
+    fn test_root(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("khzeb_test_{name}_{}", std::process::id()))
+    }
+
+    struct TextLoader;
+
+    impl AssetLoader for TextLoader {
+        type Asset = String;
+
+        fn extensions(&self) -> &[&str] {
+            &["txt"]
+        }
+
+        fn load(&self, bytes: &[u8], _: &Name) -> Result<String, AssetError> {
+            String::from_utf8(bytes.to_vec()).map_err(|err| AssetError::Format(err.to_string()))
+        }
+    }
+
+    struct PanickingLoader;
+
+    impl AssetLoader for PanickingLoader {
+        type Asset = String;
+
+        fn extensions(&self) -> &[&str] {
+            &["boom"]
+        }
+
+        fn load(&self, _: &[u8], _: &Name) -> Result<String, AssetError> {
+            panic!("boom")
+        }
+    }
+
+    #[test]
+    fn test_loader_panic() {
+        let root = test_root("loader_panic");
+        fs::create_dir_all(&root).unwrap();
+        fs::write(root.join("a.boom"), "").unwrap();
+
+        let mut server = AssetServer::new(&root);
+        server.add_loader(PanickingLoader);
+        let a = server.load::<String>("a.boom").unwrap();
+        server.wait();
+        fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(
+            server.load_state(&a),
+            Some(&LoadState::Failed(AssetError::Panic("boom".to_string())))
+        );
+    }
+
+    #[test]
+    fn test_load() {
+        let root = test_root("load");
+        fs::create_dir_all(root.join("text")).unwrap();
+        fs::write(root.join("text/greeting.txt"), "hello").unwrap();
+        fs::write(root.join("text/binary.txt"), [0xff, 0xfe]).unwrap();
+        fs::write(root.join("creatures.prefab"), r#"{ "goblin": {} }"#).unwrap();
+
+        let mut server = AssetServer::new(&root);
+        server.add_loader(TextLoader).add_loader(PrefabLoader);
+
+        let greeting = server.load::<String>("text/greeting.txt").unwrap();
+        let binary = server.load::<String>("text/binary.txt").unwrap();
+        let missing = server.load::<String>("text/missing.txt").unwrap();
+        let creatures = server.load::<Prefabs>("creatures.prefab").unwrap();
+        assert_eq!(server.load_state(&greeting), Some(&LoadState::Loading));
+        assert_eq!(server.get(&greeting), None);
+
+        assert_eq!(
+            server.load::<String>("text/greeting.md"),
+            Err(AssetError::NoLoader(Name::new("text/greeting.md")))
+        );
+        assert!(matches!(
+            server.load::<Transform>("text/other.txt"),
+            Err(AssetError::TypeMismatch { .. })
+        ));
+        // Checked for names requested before as well
+        assert!(matches!(
+            server.load::<Prefabs>("text/greeting.txt"),
+            Err(AssetError::TypeMismatch { .. })
+        ));
+
+        server.wait();
+
+        assert_eq!(server.load_state(&greeting), Some(&LoadState::Loaded));
+        assert_eq!(server.get(&greeting).map(String::as_str), Some("hello"));
+        assert!(server.get(&creatures).unwrap().get("goblin").is_some());
+        assert!(matches!(
+            server.load_state(&binary),
+            Some(LoadState::Failed(AssetError::Format(_)))
+        ));
+        assert!(matches!(
+            server.load_state(&missing),
+            Some(LoadState::Failed(AssetError::Io(_)))
+        ));
+
+        // Requesting it again hands out the loaded asset
+        let again = server.load::<String>("text/greeting.txt").unwrap();
+        assert_eq!(server.get(&again).map(String::as_str), Some("hello"));
+
+        // Failed loads are tried again
+        fs::write(root.join("text/missing.txt"), "found").unwrap();
+        server.load::<String>("text/missing.txt").unwrap();
+        assert_eq!(server.load_state(&missing), Some(&LoadState::Loading));
+        server.wait();
+        fs::remove_dir_all(&root).unwrap();
+        assert_eq!(server.get(&missing).map(String::as_str), Some("found"));
+
+        let baked = server.add("text/baked", "baked".to_string());
+        server.update();
+        assert_eq!(server.load_state(&baked), Some(&LoadState::Loaded));
+        server.get_mut(&baked).unwrap().push('!');
+        assert_eq!(server.get(&baked).map(String::as_str), Some("baked!"));
+        // Known without a loader for it
+        assert_eq!(server.load::<String>("text/baked"), Ok(baked));
+        assert!(matches!(
+            server.load::<u32>("text/baked"),
+            Err(AssetError::TypeMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn test_unload() {
+        let root = test_root("unload");
+        fs::create_dir_all(&root).unwrap();
+        fs::write(root.join("a.txt"), "a").unwrap();
+        fs::write(root.join("b.txt"), "b").unwrap();
+
+        let mut world = World::new();
+        let mut server = AssetServer::new(&root);
+        server.add_loader(TextLoader);
+        let a = server.load::<String>("a.txt").unwrap();
+        let b = server.load::<String>("b.txt").unwrap();
+        server.wait();
+        world.insert_resource(server);
+
+        world.update_assets();
+        let mut loaded: Vec<_> = world
+            .resource::<Events<AssetEvent>>()
+            .unwrap()
+            .iter()
+            .map(|(_, event)| event.clone())
+            .collect();
+        loaded.sort_by_key(|event| format!("{event:?}"));
+        assert_eq!(
+            loaded,
+            [
+                AssetEvent::Loaded(Name::new("a.txt")),
+                AssetEvent::Loaded(Name::new("b.txt"))
+            ]
+        );
+
+        let weak = a.downgrade();
+        drop(a);
+        let mut server = world.resource_mut::<AssetServer>().unwrap();
+        assert_eq!(server.update(), [AssetEvent::Freed(Name::new("a.txt"))]);
+        assert_eq!(server.load_state(&weak), None);
+        assert_eq!(server.get(&weak), None);
+        assert_eq!(server.get(&b).map(String::as_str), Some("b"));
+
+        // Dropped while loading, never shows up
+        drop(server.load::<String>("a.txt").unwrap());
+        server.wait();
+        assert!(server.update().is_empty());
+        assert_eq!(server.load_state(&weak), None);
+
+        // Loaded again from scratch
+        let a = server.load::<String>("a.txt").unwrap();
+        server.wait();
+        fs::remove_dir_all(&root).unwrap();
+        assert_eq!(server.get(&a).map(String::as_str), Some("a"));
+    }
+
+    #[test]
+    fn test_hot_reload() {
+        let root = test_root("hot_reload");
+        fs::create_dir_all(root.join("text")).unwrap();
+        fs::write(root.join("text/a.txt"), "a").unwrap();
+
+        let mut server = AssetServer::new(&root);
+        server.add_loader(TextLoader);
+        // Events of the real watcher arrive whenever the OS gets to them, only start it
+        server.watch().unwrap();
+        assert!(server.is_watching());
+        let (watcher, changes) = AssetWatcher::from_channel(&root);
+        server.watcher = Some(watcher);
+
+        let a = server.load::<String>("text/a.txt").unwrap();
+        server.wait();
+        assert_eq!(
+            server.update(),
+            [AssetEvent::Loaded(Name::new("text/a.txt"))]
+        );
+
+        fs::write(root.join("text/a.txt"), "b").unwrap();
+        // Saving a file tends to come in more than one event
+        changes.send(root.join("text/a.txt")).unwrap();
+        changes.send(root.join("text/a.txt")).unwrap();
+        // Untouched by files that are not in use
+        fs::write(root.join("text/other.txt"), "c").unwrap();
+        changes.send(root.join("text/other.txt")).unwrap();
+
+        let mut events = server.update();
+        server.wait();
+        events.extend(server.update());
+        fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(events, [AssetEvent::Modified(Name::new("text/a.txt"))]);
+        assert_eq!(server.get(&a).map(String::as_str), Some("b"));
+        assert_eq!(server.get(&Resource::<String>::new("text/other.txt")), None);
+
+        // Failing to reload keeps the previous version around
+        changes.send(root.join("text/a.txt")).unwrap();
+        server.update();
+        server.wait();
+        assert_eq!(server.load_state(&a), Some(&LoadState::Loaded));
+        assert!(matches!(
+            server.update()[..],
+            [AssetEvent::ReloadFailed(_, AssetError::Io(_))]
+        ));
+        assert_eq!(server.get(&a).map(String::as_str), Some("b"));
+        assert!(!server.reload(&Name::new("text/other.txt")));
+    }
+
+    #[test]
+    fn test_reload_races() {
+        let root = test_root("reload_races");
+        fs::create_dir_all(&root).unwrap();
+        fs::write(root.join("a.txt"), "a").unwrap();
+
+        let mut server = AssetServer::new(&root);
+        server.add_loader(TextLoader);
+        let a = server.load::<String>("a.txt").unwrap();
+
+        // Changed while loading for the first time, read again right after
+        server.file_changed(a.name());
+        server.wait();
+        assert_eq!(
+            server.update(),
+            [
+                AssetEvent::Loaded(Name::new("a.txt")),
+                AssetEvent::Modified(Name::new("a.txt"))
+            ]
+        );
+
+        // Results of a load that was superseded are dropped
+        let stale = server.entries[a.name()].generation;
+        fs::write(root.join("a.txt"), "b").unwrap();
+        assert!(server.reload(a.name()));
+        server.wait();
+        server.pending += 1;
+        server.finish(LoadResult {
+            name: a.name().clone(),
+            generation: stale,
+            result: Ok(Box::new("stale".to_string())),
+        });
+        fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(server.get(&a).map(String::as_str), Some("b"));
+        assert_eq!(server.update(), [AssetEvent::Modified(Name::new("a.txt"))]);
+    }
+}
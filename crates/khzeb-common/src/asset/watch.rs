@@ -0,0 +1,90 @@
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+    sync::{
+        mpsc::{self, Receiver},
+        Mutex,
+    },
+};
+
+use notify::{Event, RecommendedWatcher, RecursiveMode, Watcher};
+
+use crate::utils::Name;
+
+use super::loader::AssetError;
+
+impl From<notify::Error> for AssetError {
+    fn from(value: notify::Error) -> Self {
+        AssetError::Watch(value.to_string())
+    }
+}
+
+// Reports the files under a directory that were written to or replaced
+pub struct AssetWatcher {
+    root: PathBuf,
+    // Dropping it stops the watching, `None` when fed by hand
+    _watcher: Option<RecommendedWatcher>,
+    // Behind a mutex only to make the watcher shareable
+    changed: Mutex<Receiver<PathBuf>>,
+}
+
+impl AssetWatcher {
+    pub fn new(root: &Path) -> Result<Self, AssetError> {
+        // Events come with absolute paths, resolved the same way
+        let root = root.canonicalize()?;
+        let (sender, receiver) = mpsc::channel();
+
+        let mut watcher = notify::recommended_watcher(move |event: notify::Result<Event>| {
+            let Ok(event) = event else {
+                return;
+            };
+            // Editors tend to save by replacing the file
+            if event.kind.is_modify() || event.kind.is_create() {
+                for path in event.paths {
+                    sender.send(path).ok();
+                }
+            }
+        })?;
+        watcher.watch(&root, RecursiveMode::Recursive)?;
+
+        Ok(Self {
+            root,
+            _watcher: Some(watcher),
+            changed: Mutex::new(receiver),
+        })
+    }
+
+    // Reports the paths sent through the channel instead of watching the file system
+    #[cfg(test)]
+    pub(crate) fn from_channel(root: &Path) -> (Self, mpsc::Sender<PathBuf>) {
+        let (sender, receiver) = mpsc::channel();
+        let watcher = Self {
+            root: root.to_path_buf(),
+            _watcher: None,
+            changed: Mutex::new(receiver),
+        };
+        (watcher, sender)
+    }
+
+    // The names of the files changed since the last call, each once
+    pub fn changed(&mut self) -> Vec<Name> {
+        let mut seen = HashSet::new();
+        self.changed
+            .get_mut()
+            .unwrap()
+            .try_iter()
+            .filter_map(|path| name_of(&self.root, &path))
+            .filter(|name| seen.insert(name.clone()))
+            .collect()
+    }
+}
+
+// Names use `/` no matter the platform
+fn name_of(root: &Path, path: &Path) -> Option<Name> {
+    let relative = path.strip_prefix(root).ok()?;
+    let segments: Vec<_> = relative
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect();
+    Some(Name::new(segments.join("/")))
+}
@@ -1,7 +1,12 @@
+// Lets the derive macros refer to `::khzeb` from within the crate itself
+extern crate self as khzeb;
+
+pub mod asset;
 pub mod utils;
 pub mod world;
 
 pub mod prelude {
+    pub use super::asset::*;
     pub use super::utils::*;
     pub use super::world::*;
 }
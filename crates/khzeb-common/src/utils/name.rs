@@ -1,26 +1,331 @@
 use std::{
-    collections::HashMap,
+    fmt,
     hash::{Hash, Hasher},
     ops::Deref,
-    sync::{Arc, RwLock},
+    ptr,
+    sync::{
+        atomic::{AtomicPtr, Ordering},
+        Mutex, OnceLock,
+    },
 };
 
-lazy_static::lazy_static! {
-    static ref REGISTRY: RwLock<HashMap<String, Arc<str>>> = Default::default();
-}
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+// Interned string, comparing and hashing in constant time.
+// Every name lives until the end of the program and gets a dense id in the order
+// names are first seen. Ids differ between processes, what leaves the process goes by
+// the text or `stable_hash` instead.
+#[derive(Clone)]
+pub struct Name(&'static Entry);
 
-#[derive(Clone, Debug)]
-pub struct Name(Arc<str>);
+struct Entry {
+    text: &'static str,
+    hash: u64,
+    id: u32,
+}
 
 impl Name {
     pub fn new(s: impl ToString) -> Self {
-        Self::from(s)
+        let s = s.to_string();
+        let hash = hash_str(&s);
+        lookup(&s, hash).unwrap_or_else(|| intern(&s, hash, || s.clone().leak()))
+    }
+
+    // Skips copying the text, literals stay where they are
+    pub fn from_static(s: &'static str) -> Self {
+        let hash = hash_str(s);
+        lookup(s, hash).unwrap_or_else(|| intern(s, hash, || s))
+    }
+
+    // The name with the id, if one was interned with it
+    pub fn from_id(id: u32) -> Option<Self> {
+        let (chunk, offset) = chunk_of(id);
+        let chunk = CHUNKS[chunk].load(Ordering::Acquire);
+        if chunk.is_null() {
+            return None;
+        }
+
+        // SAFETY: chunks are never freed and hold `chunk_len` slots
+        let slot = unsafe { &*chunk.add(offset) };
+        let entry = slot.load(Ordering::Acquire);
+        // SAFETY: entries are leaked, so they outlive every name
+        (!entry.is_null()).then(|| Name(unsafe { &*entry }))
+    }
+
+    pub fn id(&self) -> u32 {
+        self.0.id
+    }
+
+    // Same for every run and platform
+    pub fn stable_hash(&self) -> u64 {
+        self.0.hash
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        self.0.text
+    }
+
+    // Every name interned so far, in the order of their ids
+    pub fn interned() -> impl Iterator<Item = Name> {
+        (0..=u32::MAX).map_while(Name::from_id)
+    }
+
+    // The interned names matching the glob, see `matches`
+    pub fn glob(pattern: &str) -> Vec<Name> {
+        Self::interned()
+            .filter(|name| name.matches(pattern))
+            .collect()
+    }
+}
+
+// Names double as paths with segments separated by `/`, like `sfx/ui/click.ogg`
+impl Name {
+    pub fn segments(&self) -> impl Iterator<Item = &'static str> {
+        self.0.text.split(SEPARATOR).filter(|s| !s.is_empty())
+    }
+
+    // `None` for names with a single segment
+    pub fn parent(&self) -> Option<Name> {
+        let text = self.0.text.trim_end_matches(SEPARATOR);
+        text.rsplit_once(SEPARATOR)
+            .map(|(parent, _)| Name::new(parent))
+    }
+
+    pub fn join(&self, path: impl AsRef<str>) -> Name {
+        let base = self.0.text.trim_end_matches(SEPARATOR);
+        let path = path.as_ref().trim_start_matches(SEPARATOR);
+
+        match (base.is_empty(), path.is_empty()) {
+            (_, true) => self.clone(),
+            (true, _) => Name::new(path),
+            _ => Name::new(format!("{base}{SEPARATOR}{path}")),
+        }
+    }
+
+    // The last segment
+    pub fn file_name(&self) -> &'static str {
+        self.segments().last().unwrap_or_default()
+    }
+
+    // The last segment without its extension, the leading dot of `.hidden` is not one
+    pub fn file_stem(&self) -> &'static str {
+        let file_name = self.file_name();
+        match file_name.rsplit_once('.') {
+            Some((stem, _)) if !stem.is_empty() => stem,
+            _ => file_name,
+        }
+    }
+
+    pub fn extension(&self) -> Option<&'static str> {
+        let file_name = self.file_name();
+        match file_name.rsplit_once('.') {
+            Some((stem, extension)) if !stem.is_empty() => Some(extension),
+            _ => None,
+        }
+    }
+
+    // Whether the name is the prefix or lies under it, going by whole segments,
+    // so `textures/` covers `textures/world00` but not `textures2/world00`
+    pub fn has_prefix(&self, prefix: impl AsRef<str>) -> bool {
+        let mut segments = self.segments();
+        Name::split(prefix.as_ref()).all(|prefix| segments.next() == Some(prefix))
+    }
+
+    // Globbing over segments: `*` matches within a segment, `?` a single character
+    // and `**` any number of whole segments
+    pub fn matches(&self, pattern: &str) -> bool {
+        let pattern: Vec<_> = Name::split(pattern).collect();
+        let segments: Vec<_> = self.segments().collect();
+        match_segments(&pattern, &segments)
+    }
+
+    fn split(path: &str) -> impl Iterator<Item = &str> {
+        path.split(SEPARATOR).filter(|s| !s.is_empty())
+    }
+}
+
+const SEPARATOR: char = '/';
+
+fn match_segments(pattern: &[&str], segments: &[&str]) -> bool {
+    match pattern.split_first() {
+        None => segments.is_empty(),
+        Some((&"**", rest)) => (0..=segments.len()).any(|i| match_segments(rest, &segments[i..])),
+        Some((first, rest)) => match segments.split_first() {
+            Some((segment, segments)) => {
+                let pattern: Vec<_> = first.chars().collect();
+                let segment: Vec<_> = segment.chars().collect();
+                match_segment(&pattern, &segment) && match_segments(rest, segments)
+            }
+            None => false,
+        },
+    }
+}
+
+fn match_segment(pattern: &[char], segment: &[char]) -> bool {
+    match pattern.split_first() {
+        None => segment.is_empty(),
+        Some(('*', rest)) => (0..=segment.len()).any(|i| match_segment(rest, &segment[i..])),
+        Some((&c, rest)) => match segment.split_first() {
+            Some((&s, segment)) => (c == '?' || c == s) && match_segment(rest, segment),
+            None => false,
+        },
+    }
+}
+
+// A name to be interned the first time it's used, for statics and constants
+pub struct StaticName {
+    text: &'static str,
+    name: OnceLock<Name>,
+}
+
+impl StaticName {
+    pub const fn new(text: &'static str) -> Self {
+        Self {
+            text,
+            name: OnceLock::new(),
+        }
+    }
+
+    pub fn get(&self) -> Name {
+        self.name
+            .get_or_init(|| Name::from_static(self.text))
+            .clone()
+    }
+}
+
+// 64-bit FNV-1a
+pub const fn hash_str(s: &str) -> u64 {
+    let bytes = s.as_bytes();
+    let mut hash = 0xcbf29ce484222325u64;
+    let mut i = 0;
+    while i < bytes.len() {
+        hash ^= bytes[i] as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+        i += 1;
+    }
+    hash
+}
+
+// Open addressing over the hashes, never more than half full.
+// Only the writer fills slots and swaps in bigger tables, readers never wait on it.
+struct Table {
+    slots: Box<[AtomicPtr<Entry>]>,
+}
+
+impl Table {
+    fn with_capacity(capacity: usize) -> Self {
+        Self {
+            slots: (0..capacity)
+                .map(|_| AtomicPtr::new(ptr::null_mut()))
+                .collect(),
+        }
+    }
+
+    fn find(&self, s: &str, hash: u64) -> Option<&'static Entry> {
+        let mask = self.slots.len() - 1;
+        let mut idx = hash as usize & mask;
+        loop {
+            let entry = self.slots[idx].load(Ordering::Acquire);
+            if entry.is_null() {
+                return None;
+            }
+
+            // SAFETY: entries are leaked, so they outlive every table
+            let entry: &'static Entry = unsafe { &*entry };
+            if entry.hash == hash && entry.text == s {
+                return Some(entry);
+            }
+            idx = (idx + 1) & mask;
+        }
+    }
+
+    // The entry must not be in the table yet
+    fn insert(&self, entry: &'static Entry) {
+        let mask = self.slots.len() - 1;
+        let mut idx = entry.hash as usize & mask;
+        while !self.slots[idx].load(Ordering::Relaxed).is_null() {
+            idx = (idx + 1) & mask;
+        }
+        self.slots[idx].store(ptr::from_ref(entry).cast_mut(), Ordering::Release);
+    }
+}
+
+static TABLE: AtomicPtr<Table> = AtomicPtr::new(ptr::null_mut());
+
+// Entries by id, chunk `n` holds ids `2^n - 1..2^(n + 1) - 1`
+static CHUNKS: [AtomicPtr<AtomicPtr<Entry>>; 33] = [const { AtomicPtr::new(ptr::null_mut()) }; 33];
+
+// The number of interned names, only touched when interning a new one
+static WRITER: Mutex<u32> = Mutex::new(0);
+
+fn chunk_of(id: u32) -> (usize, usize) {
+    let n = id as u64 + 1;
+    let chunk = 63 - n.leading_zeros() as usize;
+    (chunk, (n - (1 << chunk)) as usize)
+}
+
+fn lookup(s: &str, hash: u64) -> Option<Name> {
+    let table = TABLE.load(Ordering::Acquire);
+    if table.is_null() {
+        return None;
+    }
+
+    // SAFETY: replaced tables are leaked, readers may still be probing them
+    unsafe { &*table }.find(s, hash).map(Name)
+}
+
+fn intern(s: &str, hash: u64, text: impl FnOnce() -> &'static str) -> Name {
+    let mut len = WRITER.lock().unwrap();
+
+    // Someone else might have interned it while we were waiting
+    if let Some(name) = lookup(s, hash) {
+        return name;
+    }
+
+    let entry: &'static Entry = Box::leak(Box::new(Entry {
+        text: text(),
+        hash,
+        id: *len,
+    }));
+
+    let (chunk, offset) = chunk_of(entry.id);
+    if CHUNKS[chunk].load(Ordering::Relaxed).is_null() {
+        let slots: Box<[AtomicPtr<Entry>]> = (0..1usize << chunk)
+            .map(|_| AtomicPtr::new(ptr::null_mut()))
+            .collect();
+        CHUNKS[chunk].store(Box::leak(slots).as_mut_ptr(), Ordering::Release);
+    }
+    // SAFETY: the chunk was allocated with room for the offset
+    let slot = unsafe { &*CHUNKS[chunk].load(Ordering::Relaxed).add(offset) };
+    slot.store(ptr::from_ref(entry).cast_mut(), Ordering::Release);
+
+    *len += 1;
+
+    let table = TABLE.load(Ordering::Relaxed);
+    // SAFETY: only the writer replaces tables
+    match unsafe { table.as_ref() } {
+        Some(table) if table.slots.len() >= *len as usize * 2 => table.insert(entry),
+        _ => {
+            let grown = Table::with_capacity((*len as usize * 4).next_power_of_two());
+            for id in 0..*len {
+                grown.insert(Name::from_id(id).unwrap().0);
+            }
+            TABLE.store(Box::into_raw(Box::new(grown)), Ordering::Release);
+        }
+    }
+
+    Name(entry)
+}
+
+impl fmt::Debug for Name {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Name").field(&self.0.text).finish()
     }
 }
 
 impl AsRef<str> for Name {
     fn as_ref(&self) -> &str {
-        &self.0
+        self.0.text
     }
 }
 
@@ -28,7 +333,7 @@ impl Deref for Name {
     type Target = str;
 
     fn deref(&self) -> &Self::Target {
-        &self.0
+        self.0.text
     }
 }
 
@@ -37,19 +342,13 @@ where
     S: ToString,
 {
     fn from(value: S) -> Self {
-        let string = value.to_string();
-        let mut registry = REGISTRY.write().unwrap();
-        let entry = registry
-            .entry(string.clone())
-            .or_insert_with(|| Arc::from(string.as_str()));
-
-        Self(entry.clone())
+        Name::new(value)
     }
 }
 
 impl PartialEq for Name {
     fn eq(&self, other: &Self) -> bool {
-        Arc::ptr_eq(&self.0, &other.0)
+        ptr::eq(self.0, other.0)
     }
 }
 
@@ -57,24 +356,39 @@ impl Eq for Name {}
 
 impl PartialEq<&str> for Name {
     fn eq(&self, other: &&str) -> bool {
-        self.0.as_ref() == *other
+        self.0.text == *other
     }
 }
 
 impl Hash for Name {
     fn hash<H: Hasher>(&self, state: &mut H) {
-        self.0.as_ptr().hash(state);
+        state.write_u64(self.0.hash);
+    }
+}
+
+// Stored as the plain string, interned again when read back
+impl Serialize for Name {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Name {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer).map(Name::new)
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::Name;
+    use std::thread;
+
+    use super::{hash_str, Name, StaticName};
 
     #[test]
     fn test_creation() {
         let from_str = Name::new("Bumblebee");
-        let from_string = Name::new("Wasp".to_string());
+        let from_string = Name::from("Wasp".to_string());
 
         assert_eq!(from_str, "Bumblebee");
         assert_eq!(from_string, "Wasp");
@@ -90,4 +404,65 @@ mod tests {
         assert_ne!(a, z);
         assert_ne!(b, z);
     }
+
+    #[test]
+    fn test_ids() {
+        static HORNET: StaticName = StaticName::new("Hornet");
+        const HASH: u64 = hash_str("Hornet");
+
+        let hornet = Name::new("Hornet");
+        assert_eq!(HORNET.get(), hornet);
+        assert_eq!(hornet.stable_hash(), HASH);
+        assert_eq!(Name::from_id(hornet.id()), Some(hornet.clone()));
+        assert_eq!(Name::from_id(u32::MAX), None);
+
+        // Interned from many threads at once, every name ends up with a single id
+        let names: Vec<Vec<Name>> = thread::scope(|s| {
+            let handles: Vec<_> = (0..4)
+                .map(|_| s.spawn(|| (0..500).map(|i| Name::new(format!("moth{i}"))).collect()))
+                .collect();
+            handles.into_iter().map(|h| h.join().unwrap()).collect()
+        });
+
+        for (i, name) in names[0].iter().enumerate() {
+            assert_eq!(&**name, format!("moth{i}"));
+            assert_eq!(Name::from_id(name.id()).as_ref(), Some(name));
+            assert!(names.iter().all(|names| names[i].id() == name.id()));
+        }
+    }
+
+    #[test]
+    fn test_paths() {
+        let click = Name::new("sfx/ui/click.ogg");
+
+        assert_eq!(
+            click.segments().collect::<Vec<_>>(),
+            ["sfx", "ui", "click.ogg"]
+        );
+        assert_eq!(click.parent(), Some(Name::new("sfx/ui")));
+        assert_eq!(Name::new("sfx").parent(), None);
+        assert_eq!(Name::new("sfx/").join("/ui").join("click.ogg"), click);
+        assert_eq!(click.file_name(), "click.ogg");
+        assert_eq!(click.file_stem(), "click");
+        assert_eq!(click.extension(), Some("ogg"));
+        assert_eq!(Name::new("cfg/.hidden").file_stem(), ".hidden");
+        assert_eq!(Name::new("cfg/.hidden").extension(), None);
+
+        assert!(click.has_prefix("sfx/ui/"));
+        assert!(click.has_prefix("sfx"));
+        assert!(click.has_prefix(""));
+        assert!(!click.has_prefix("sfx/u"));
+        assert!(!Name::new("sfx2/ui").has_prefix("sfx"));
+
+        assert!(click.matches("sfx/*/click.*"));
+        assert!(click.matches("sfx/**"));
+        assert!(click.matches("**/cl?ck.ogg"));
+        assert!(!click.matches("*/click.ogg"));
+        assert!(!click.matches("sfx/ui/click.og?/**/x"));
+
+        Name::new("sfx/ui/hover.ogg");
+        let mut ui = Name::glob("sfx/ui/*.ogg");
+        ui.sort_by_key(|name| name.as_str());
+        assert_eq!(ui, [click, Name::new("sfx/ui/hover.ogg")]);
+    }
 }
@@ -1,18 +1,190 @@
-use std::{any::Any, collections::HashMap, marker::PhantomData};
+use std::{
+    any::Any,
+    collections::HashMap,
+    fmt,
+    marker::PhantomData,
+    ops::Deref,
+    sync::{
+        mpsc::{self, Receiver, Sender},
+        Arc, Mutex, Weak,
+    },
+};
 
 use super::Name;
 
 // Name bound to type information
-#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Resource<R> {
     name: Name,
-    _phantom_data: PhantomData<R>,
+    _phantom_data: PhantomData<fn() -> R>,
+}
+
+// Not derived, that would require the same of `R`
+impl<R> Clone for Resource<R> {
+    fn clone(&self) -> Self {
+        Self::new(self.name.clone())
+    }
+}
+
+impl<R> fmt::Debug for Resource<R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Resource").field(&self.name).finish()
+    }
+}
+
+impl<R> PartialEq for Resource<R> {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name
+    }
+}
+
+impl<R> Eq for Resource<R> {}
+
+impl<R> Resource<R> {
+    pub fn new(name: impl Into<Name>) -> Self {
+        Self {
+            name: name.into(),
+            _phantom_data: PhantomData,
+        }
+    }
+
+    pub fn name(&self) -> &Name {
+        &self.name
+    }
+}
+
+// Shared by the strong handles of a resource, reports the drop of the last one
+struct HandleToken {
+    name: Name,
+    dropped: Sender<Name>,
+}
+
+impl Drop for HandleToken {
+    fn drop(&mut self) {
+        // The registry is gone along with the resource
+        self.dropped.send(self.name.clone()).ok();
+    }
+}
+
+// Keeps a resource of the registry alive, it's freed once the last strong handle is dropped
+pub struct Handle<R> {
+    resource: Resource<R>,
+    token: Arc<HandleToken>,
+}
+
+impl<R> Handle<R> {
+    pub fn resource(&self) -> &Resource<R> {
+        &self.resource
+    }
+
+    pub fn downgrade(&self) -> WeakHandle<R> {
+        WeakHandle {
+            resource: self.resource.clone(),
+            token: Arc::downgrade(&self.token),
+        }
+    }
+
+    pub fn strong_count(&self) -> usize {
+        Arc::strong_count(&self.token)
+    }
+}
+
+impl<R> Clone for Handle<R> {
+    fn clone(&self) -> Self {
+        Self {
+            resource: self.resource.clone(),
+            token: self.token.clone(),
+        }
+    }
+}
+
+impl<R> fmt::Debug for Handle<R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Handle").field(self.resource.name()).finish()
+    }
+}
+
+impl<R> PartialEq for Handle<R> {
+    fn eq(&self, other: &Self) -> bool {
+        self.resource == other.resource
+    }
+}
+
+impl<R> Eq for Handle<R> {}
+
+impl<R> Deref for Handle<R> {
+    type Target = Resource<R>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.resource
+    }
+}
+
+// Refers to a resource without keeping it alive
+pub struct WeakHandle<R> {
+    resource: Resource<R>,
+    token: Weak<HandleToken>,
+}
+
+impl<R> WeakHandle<R> {
+    pub fn resource(&self) -> &Resource<R> {
+        &self.resource
+    }
+
+    // `None` once every strong handle is dropped
+    pub fn upgrade(&self) -> Option<Handle<R>> {
+        self.token.upgrade().map(|token| Handle {
+            resource: self.resource.clone(),
+            token,
+        })
+    }
+}
+
+impl<R> Clone for WeakHandle<R> {
+    fn clone(&self) -> Self {
+        Self {
+            resource: self.resource.clone(),
+            token: self.token.clone(),
+        }
+    }
+}
+
+impl<R> fmt::Debug for WeakHandle<R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("WeakHandle")
+            .field(self.resource.name())
+            .finish()
+    }
+}
+
+impl<R> Deref for WeakHandle<R> {
+    type Target = Resource<R>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.resource
+    }
 }
 
 // Generic untyped resource registry.
-#[derive(Default)]
+// Shareable between threads so it can be stored as a world resource.
+// Resources handed out through `Handle`s are counted, the rest are kept until removed.
 pub struct Registry {
-    resources: HashMap<Name, Box<dyn Any>>,
+    resources: HashMap<Name, Box<dyn Any + Send + Sync>>,
+    tokens: HashMap<Name, Weak<HandleToken>>,
+    dropped: Sender<Name>,
+    // Behind a mutex only to keep the registry shareable
+    dropped_receiver: Mutex<Receiver<Name>>,
+}
+
+impl Default for Registry {
+    fn default() -> Self {
+        let (dropped, dropped_receiver) = mpsc::channel();
+        Self {
+            resources: Default::default(),
+            tokens: Default::default(),
+            dropped,
+            dropped_receiver: Mutex::new(dropped_receiver),
+        }
+    }
 }
 
 impl Registry {
@@ -20,28 +192,172 @@ impl Registry {
         Default::default()
     }
 
-    pub fn put<R: 'static>(&mut self, name: impl Into<Name>, resource: R) -> Resource<R> {
+    // Puts the resource in to be freed along with the last of its handles
+    pub fn put_counted<R: Send + Sync + 'static>(
+        &mut self,
+        name: impl Into<Name>,
+        resource: R,
+    ) -> Handle<R> {
         let name = name.into();
+        self.resources.insert(name.clone(), Box::new(resource));
+        self.handle(name)
+    }
+
+    // A strong handle to the name, whether the resource is in yet or not.
+    // From then on the resource is counted, even if it was put in to be kept.
+    pub fn handle<R>(&mut self, name: impl Into<Name>) -> Handle<R> {
+        let resource = Resource::new(name);
+        if let Some(token) = self.tokens.get(&resource.name).and_then(Weak::upgrade) {
+            return Handle { resource, token };
+        }
+
+        let token = Arc::new(HandleToken {
+            name: resource.name.clone(),
+            dropped: self.dropped.clone(),
+        });
+        self.tokens
+            .insert(resource.name.clone(), Arc::downgrade(&token));
+        Handle { resource, token }
+    }
 
-        let res = Resource {
-            name: name.clone(),
-            _phantom_data: Default::default(),
-        };
+    // Drops the counted resources without strong handles left, returning their names
+    pub fn free_unused(&mut self) -> Vec<Name> {
+        let dropped: Vec<_> = self
+            .dropped_receiver
+            .get_mut()
+            .unwrap()
+            .try_iter()
+            .collect();
 
-        self.resources.insert(name, Box::new(resource));
+        let mut freed = vec![];
+        for name in dropped {
+            // The name might have been handed out again in the meantime
+            let unused = self
+                .tokens
+                .get(&name)
+                .is_some_and(|token| token.strong_count() == 0);
+            if !unused {
+                continue;
+            }
+
+            self.tokens.remove(&name);
+            if self.resources.remove(&name).is_some() {
+                freed.push(name);
+            }
+        }
+        freed
+    }
+
+    pub fn remove(&mut self, name: &Name) -> bool {
+        self.tokens.remove(name);
+        self.resources.remove(name).is_some()
+    }
+
+    // Whether the name was handed out through handles and not freed since
+    pub fn is_counted(&self, name: &Name) -> bool {
+        self.tokens.contains_key(name)
+    }
 
+    pub fn strong_count(&self, name: &Name) -> usize {
+        self.tokens.get(name).map_or(0, Weak::strong_count)
+    }
+
+    pub fn contains(&self, name: &Name) -> bool {
+        self.resources.contains_key(name)
+    }
+
+    pub fn put<R: Send + Sync + 'static>(
+        &mut self,
+        name: impl Into<Name>,
+        resource: R,
+    ) -> Resource<R> {
+        let res = Resource::new(name);
+        self.resources.insert(res.name.clone(), Box::new(resource));
         res
     }
 
+    pub(crate) fn put_boxed(&mut self, name: Name, resource: Box<dyn Any + Send + Sync>) {
+        self.resources.insert(name, resource);
+    }
+
     pub fn get<R: 'static>(&self, name: Resource<R>) -> Option<&R> {
         self.resources
             .get(&name.name)
             .and_then(|b| b.downcast_ref::<R>())
     }
 
+    // The names of resources at or under the prefix, like `textures/`
+    pub fn list<'a>(&'a self, prefix: &'a str) -> impl Iterator<Item = &'a Name> {
+        self.resources
+            .keys()
+            .filter(move |name| name.has_prefix(prefix))
+    }
+
+    // The names of resources matching the glob, see `Name::matches`
+    pub fn glob<'a>(&'a self, pattern: &'a str) -> impl Iterator<Item = &'a Name> {
+        self.resources
+            .keys()
+            .filter(move |name| name.matches(pattern))
+    }
+
     pub fn get_mut<R: 'static>(&mut self, name: Resource<R>) -> Option<&mut R> {
         self.resources
             .get_mut(&name.name)
             .and_then(|b| b.downcast_mut::<R>())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_counted() {
+        let mut registry = Registry::new();
+        let kept = registry.put("textures/kept", 0);
+        let a = registry.put_counted("textures/a", 1);
+        let a2 = a.clone();
+        let weak = a.downgrade();
+        assert_eq!(a.strong_count(), 2);
+
+        drop(a);
+        assert!(registry.free_unused().is_empty());
+        assert_eq!(registry.get(weak.resource().clone()), Some(&1));
+
+        // A handle to a name that is not in yet keeps it once it is
+        let pending = registry.handle::<i32>("textures/b");
+        registry.put("textures/b", 2);
+
+        drop(a2);
+        drop(pending);
+        let mut freed = registry.free_unused();
+        freed.sort_by_key(|name| name.as_str());
+        assert_eq!(freed, [Name::new("textures/a"), Name::new("textures/b")]);
+        assert!(weak.upgrade().is_none());
+        assert_eq!(registry.get(weak.resource().clone()), None);
+        assert_eq!(registry.get(kept), Some(&0));
+
+        // Dropped and handed out again before freeing
+        let c = registry.put_counted("textures/c", 3);
+        drop(c);
+        let c = registry.handle::<i32>("textures/c");
+        assert!(registry.free_unused().is_empty());
+        assert_eq!(registry.get(c.resource().clone()), Some(&3));
+    }
+
+    #[test]
+    fn test_list() {
+        let mut registry = Registry::new();
+        registry.put("textures/world00", 0);
+        registry.put("textures/ui/cursor", 1);
+        registry.put("textures2/world00", 2);
+        registry.put("sfx/ui/click", 3);
+
+        let mut textures: Vec<_> = registry.list("textures/").map(Name::as_str).collect();
+        textures.sort();
+        assert_eq!(textures, ["textures/ui/cursor", "textures/world00"]);
+
+        let ui: Vec<_> = registry.glob("*/ui/*").map(Name::as_str).collect();
+        assert_eq!(ui.len(), 2);
+    }
+}
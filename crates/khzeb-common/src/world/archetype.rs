@@ -0,0 +1,438 @@
+use std::{
+    any::{Any, TypeId},
+    cell::UnsafeCell,
+    collections::HashMap,
+};
+
+use crate::utils::Name;
+
+use super::{
+    change::{ComponentTicks, Tick},
+    entity::Entity,
+};
+
+pub type ArchetypeId = usize;
+pub type ArchetypeRow = usize;
+
+// The archetype every entity starts in, the one without components
+pub const EMPTY_ARCHETYPE: ArchetypeId = 0;
+
+// Type-erased contiguous storage of a single component type.
+// Values sit in `UnsafeCell`s so queries can hand out mutable borrows of distinct columns.
+pub(crate) trait ColumnData: Any + Send {
+    fn len(&self) -> usize;
+
+    // Drops the value at `row`, moving the last value into its place
+    fn swap_remove(&mut self, row: ArchetypeRow);
+
+    // Moves the value at `row` to the end of `target`, which must hold the same type
+    fn swap_remove_into(&mut self, row: ArchetypeRow, target: &mut dyn ColumnData);
+
+    fn new_empty(&self) -> Box<dyn ColumnData>;
+
+    // The type of the values, not of the storage
+    fn item_type_id(&self) -> TypeId;
+
+    fn as_any(&self) -> &dyn Any;
+
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+impl<T: Send + Sync + 'static> ColumnData for Vec<UnsafeCell<T>> {
+    fn len(&self) -> usize {
+        Vec::len(self)
+    }
+
+    fn swap_remove(&mut self, row: ArchetypeRow) {
+        Vec::swap_remove(self, row);
+    }
+
+    fn swap_remove_into(&mut self, row: ArchetypeRow, target: &mut dyn ColumnData) {
+        let target = target
+            .as_any_mut()
+            .downcast_mut::<Vec<UnsafeCell<T>>>()
+            .expect("Columns of mismatched types");
+        target.push(Vec::swap_remove(self, row));
+    }
+
+    fn new_empty(&self) -> Box<dyn ColumnData> {
+        Box::new(Vec::<UnsafeCell<T>>::new())
+    }
+
+    fn item_type_id(&self) -> TypeId {
+        TypeId::of::<T>()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+pub struct Column {
+    data: Box<dyn ColumnData>,
+    // When each value was added and last changed, parallel to `data`
+    ticks: Vec<UnsafeCell<ComponentTicks>>,
+}
+
+// SAFETY: columns only hold `Sync` values and the cells are accessed according to
+// the access rules of queries and systems
+unsafe impl Sync for Column {}
+
+impl Column {
+    pub fn new<T: Send + Sync + 'static>() -> Self {
+        Self {
+            data: Box::new(Vec::<UnsafeCell<T>>::new()),
+            ticks: vec![],
+        }
+    }
+
+    pub fn new_empty(&self) -> Self {
+        Self {
+            data: self.data.new_empty(),
+            ticks: vec![],
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // Drops the value at `row`, moving the last value into its place
+    pub fn swap_remove(&mut self, row: ArchetypeRow) {
+        self.data.swap_remove(row);
+        self.ticks.swap_remove(row);
+    }
+
+    // Takes the value at `row` out, moving the last value into its place
+    pub fn swap_remove_take<T: 'static>(&mut self, row: ArchetypeRow) -> Option<T> {
+        let value = self
+            .as_vec_mut::<T>()
+            .map(|v| v.swap_remove(row).into_inner())?;
+        self.ticks.swap_remove(row);
+        Some(value)
+    }
+
+    // Moves the value at `row` to the end of `target`, which must hold the same type
+    fn swap_remove_into(&mut self, row: ArchetypeRow, target: &mut Column) {
+        self.data.swap_remove_into(row, target.data.as_mut());
+        target.ticks.push(self.ticks.swap_remove(row));
+    }
+
+    pub(crate) fn push<T: 'static>(&mut self, value: T, tick: Tick) -> Option<()> {
+        self.as_vec_mut::<T>()?.push(UnsafeCell::new(value));
+        self.ticks.push(UnsafeCell::new(ComponentTicks::new(tick)));
+        Some(())
+    }
+
+    pub fn get_mut<T: 'static>(
+        &mut self,
+        row: ArchetypeRow,
+    ) -> Option<(&mut T, &mut ComponentTicks)> {
+        let values = self
+            .data
+            .as_any_mut()
+            .downcast_mut::<Vec<UnsafeCell<T>>>()?;
+        let value = values.get_mut(row)?.get_mut();
+        let ticks = self.ticks[row].get_mut();
+        Some((value, ticks))
+    }
+
+    pub fn ticks(&self, row: ArchetypeRow) -> Option<ComponentTicks> {
+        // SAFETY: ticks are only written through exclusive borrows of the value they track
+        self.ticks.get(row).map(|t| unsafe { *t.get() })
+    }
+
+    // The cells are only ever dereferenced according to the access rules of queries
+    pub(crate) fn tick_cells(&self) -> &[UnsafeCell<ComponentTicks>] {
+        &self.ticks
+    }
+
+    // Whether the column stores values of type `T`
+    pub fn is<T: 'static>(&self) -> bool {
+        self.data.as_any().is::<Vec<UnsafeCell<T>>>()
+    }
+
+    pub(crate) fn item_type_id(&self) -> TypeId {
+        self.data.item_type_id()
+    }
+
+    pub fn as_slice<T: 'static>(&self) -> Option<&[T]> {
+        self.as_cells::<T>().map(|cells| {
+            // SAFETY: mutable access to the cells requires an exclusive borrow of the world
+            unsafe { &*(cells as *const [UnsafeCell<T>] as *const [T]) }
+        })
+    }
+
+    // The cells are only ever dereferenced according to the access rules of queries
+    pub(crate) fn as_cells<T: 'static>(&self) -> Option<&[UnsafeCell<T>]> {
+        self.data
+            .as_any()
+            .downcast_ref::<Vec<UnsafeCell<T>>>()
+            .map(Vec::as_slice)
+    }
+
+    fn as_vec_mut<T: 'static>(&mut self) -> Option<&mut Vec<UnsafeCell<T>>> {
+        self.data.as_any_mut().downcast_mut::<Vec<UnsafeCell<T>>>()
+    }
+}
+
+pub struct Archetype {
+    id: ArchetypeId,
+    // Sorted by their string representation, parallel to `columns`
+    names: Vec<Name>,
+    columns: Vec<Column>,
+    entities: Vec<Entity>,
+
+    // Cached transitions to the archetypes with one more or one less component
+    add_edges: HashMap<Name, ArchetypeId>,
+    remove_edges: HashMap<Name, ArchetypeId>,
+}
+
+impl Archetype {
+    fn new(id: ArchetypeId, names: Vec<Name>, columns: Vec<Column>) -> Self {
+        Self {
+            id,
+            names,
+            columns,
+            entities: vec![],
+            add_edges: Default::default(),
+            remove_edges: Default::default(),
+        }
+    }
+
+    pub fn id(&self) -> ArchetypeId {
+        self.id
+    }
+
+    pub fn names(&self) -> &[Name] {
+        &self.names
+    }
+
+    pub fn entities(&self) -> &[Entity] {
+        &self.entities
+    }
+
+    pub fn len(&self) -> usize {
+        self.entities.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entities.is_empty()
+    }
+
+    pub fn has(&self, name: &Name) -> bool {
+        self.column_index(name).is_some()
+    }
+
+    pub fn column(&self, name: &Name) -> Option<&Column> {
+        self.column_index(name).map(|i| &self.columns[i])
+    }
+
+    pub fn column_mut(&mut self, name: &Name) -> Option<&mut Column> {
+        self.column_index(name).map(|i| &mut self.columns[i])
+    }
+
+    fn column_index(&self, name: &Name) -> Option<usize> {
+        self.names.binary_search_by(|n| (**n).cmp(&**name)).ok()
+    }
+
+    // Pushes a component into the column for `name`, the caller completes the row
+    pub(crate) fn push_component<T: Send + Sync + 'static>(
+        &mut self,
+        name: &Name,
+        component: T,
+        tick: Tick,
+    ) {
+        self.column_mut(name)
+            .and_then(|column| column.push(component, tick))
+            .expect("Component does not match the column type");
+    }
+
+    pub(crate) fn push_entity(&mut self, entity: Entity) -> ArchetypeRow {
+        self.entities.push(entity);
+        self.entities.len() - 1
+    }
+
+    // Removes the row, dropping its components. Returns the entity moved into its place.
+    pub(crate) fn swap_remove(&mut self, row: ArchetypeRow) -> Option<Entity> {
+        for column in self.columns.iter_mut() {
+            column.swap_remove(row);
+        }
+
+        self.entities.swap_remove(row);
+        self.entities.get(row).copied()
+    }
+
+    // Moves the row into `target`, handing components `target` lacks to `leftover`,
+    // which must remove the row from the column it is given.
+    // Returns the new row in `target` and the entity moved into the old row.
+    pub(crate) fn move_row(
+        &mut self,
+        row: ArchetypeRow,
+        target: &mut Archetype,
+        mut leftover: impl FnMut(&Name, &mut Column),
+    ) -> (ArchetypeRow, Option<Entity>) {
+        for (name, column) in self.names.iter().zip(self.columns.iter_mut()) {
+            match target.column_mut(name) {
+                Some(target_column) => column.swap_remove_into(row, target_column),
+                None => leftover(name, column),
+            }
+        }
+
+        let entity = self.entities.swap_remove(row);
+        let new_row = target.push_entity(entity);
+
+        (new_row, self.entities.get(row).copied())
+    }
+}
+
+pub struct Archetypes {
+    archetypes: Vec<Archetype>,
+    lookup: HashMap<Vec<Name>, ArchetypeId>,
+}
+
+impl Default for Archetypes {
+    fn default() -> Self {
+        let empty = Archetype::new(EMPTY_ARCHETYPE, vec![], vec![]);
+
+        Self {
+            archetypes: vec![empty],
+            lookup: [(vec![], EMPTY_ARCHETYPE)].into_iter().collect(),
+        }
+    }
+}
+
+impl Archetypes {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get(&self, id: ArchetypeId) -> Option<&Archetype> {
+        self.archetypes.get(id)
+    }
+
+    pub fn get_mut(&mut self, id: ArchetypeId) -> Option<&mut Archetype> {
+        self.archetypes.get_mut(id)
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, Archetype> {
+        self.archetypes.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.archetypes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.archetypes.is_empty()
+    }
+
+    // Borrows two distinct archetypes mutably at once
+    pub fn pair_mut(&mut self, a: ArchetypeId, b: ArchetypeId) -> (&mut Archetype, &mut Archetype) {
+        assert_ne!(a, b, "Can not borrow the same archetype twice");
+
+        if a < b {
+            let (left, right) = self.archetypes.split_at_mut(b);
+            (&mut left[a], &mut right[0])
+        } else {
+            let (left, right) = self.archetypes.split_at_mut(a);
+            (&mut right[0], &mut left[b])
+        }
+    }
+
+    // Finds or creates the archetype of `from` extended with a component of type `T`
+    pub fn with_component<T: Send + Sync + 'static>(
+        &mut self,
+        from: ArchetypeId,
+        name: &Name,
+    ) -> ArchetypeId {
+        if let Some(&id) = self.archetypes[from].add_edges.get(name) {
+            return id;
+        }
+
+        let source = &self.archetypes[from];
+        let mut names = source.names.clone();
+        let idx = names
+            .binary_search_by(|n| (**n).cmp(&**name))
+            .unwrap_or_else(|i| i);
+        names.insert(idx, name.clone());
+
+        let id = match self.lookup.get(&names) {
+            Some(&id) => id,
+            None => {
+                let mut columns: Vec<Column> =
+                    source.columns.iter().map(Column::new_empty).collect();
+                columns.insert(idx, Column::new::<T>());
+                self.insert(names, columns)
+            }
+        };
+
+        self.archetypes[from].add_edges.insert(name.clone(), id);
+        self.archetypes[id].remove_edges.insert(name.clone(), from);
+
+        id
+    }
+
+    // Finds or creates the archetype of `from` without the component `name`
+    pub fn without_component(&mut self, from: ArchetypeId, name: &Name) -> ArchetypeId {
+        if let Some(&id) = self.archetypes[from].remove_edges.get(name) {
+            return id;
+        }
+
+        let source = &self.archetypes[from];
+        let Some(idx) = source.column_index(name) else {
+            return from;
+        };
+
+        let mut names = source.names.clone();
+        names.remove(idx);
+
+        let id = match self.lookup.get(&names) {
+            Some(&id) => id,
+            None => {
+                let columns = source
+                    .columns
+                    .iter()
+                    .enumerate()
+                    .filter(|(i, _)| *i != idx)
+                    .map(|(_, c)| c.new_empty())
+                    .collect();
+                self.insert(names, columns)
+            }
+        };
+
+        self.archetypes[from].remove_edges.insert(name.clone(), id);
+        self.archetypes[id].add_edges.insert(name.clone(), from);
+
+        id
+    }
+
+    // Finds or creates the archetype with exactly the names, which must be sorted,
+    // `columns` are only created for a new one and have to be parallel to the names
+    pub(crate) fn get_or_insert(
+        &mut self,
+        names: Vec<Name>,
+        columns: impl FnOnce() -> Vec<Column>,
+    ) -> ArchetypeId {
+        match self.lookup.get(&names) {
+            Some(&id) => id,
+            None => self.insert(names, columns()),
+        }
+    }
+
+    fn insert(&mut self, names: Vec<Name>, columns: Vec<Column>) -> ArchetypeId {
+        let id = self.archetypes.len();
+        self.archetypes
+            .push(Archetype::new(id, names.clone(), columns));
+        self.lookup.insert(names, id);
+        id
+    }
+}
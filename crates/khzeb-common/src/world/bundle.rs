@@ -0,0 +1,330 @@
+use std::any::{type_name, TypeId};
+
+use crate::utils::Name;
+
+use super::{
+    archetype::{Archetype, ArchetypeId, Archetypes, Column},
+    change::Tick,
+    component::{register_component_type, Component, NameCollision, StorageType},
+    entity::Entity,
+    error::{WorldError, WorldResult},
+    observer::ComponentEvent,
+    sparse_set::SparseSets,
+    world::World,
+};
+
+pub use khzeb_derive::Bundle;
+
+// What placing a bundle needs to know about one of its components
+pub struct BundleComponent {
+    name: Name,
+    storage: StorageType,
+    type_id: TypeId,
+    type_name: &'static str,
+    new_column: fn() -> Column,
+    register: fn(&Name) -> Result<(), NameCollision>,
+}
+
+impl BundleComponent {
+    pub fn of<C: Component>(component: &C) -> Self {
+        Self {
+            name: component.name(),
+            storage: C::storage_type(),
+            type_id: TypeId::of::<C>(),
+            type_name: type_name::<C>(),
+            new_column: Column::new::<C>,
+            register: register_component_type::<C>,
+        }
+    }
+
+    pub fn name(&self) -> &Name {
+        &self.name
+    }
+}
+
+// Components added to an entity in one go.
+// Every component is a bundle, and so are tuples of bundles and structs deriving `Bundle`.
+pub trait Bundle: Send + Sync + 'static {
+    // Describes the components in the order `push` pushes them
+    fn components(&self, components: &mut Vec<BundleComponent>);
+
+    // Pushes every component into its column or sparse set, the caller completes the row.
+    // Fails on a sparse set of another type, which `bundle_archetype` rules out beforehand.
+    fn push(
+        self,
+        entity: Entity,
+        archetype: &mut Archetype,
+        sparse_sets: &mut SparseSets,
+        tick: Tick,
+    ) -> WorldResult<()>;
+}
+
+impl<C: Component> Bundle for C {
+    fn components(&self, components: &mut Vec<BundleComponent>) {
+        components.push(BundleComponent::of(self));
+    }
+
+    fn push(
+        self,
+        entity: Entity,
+        archetype: &mut Archetype,
+        sparse_sets: &mut SparseSets,
+        tick: Tick,
+    ) -> WorldResult<()> {
+        let name = self.name();
+
+        match C::storage_type() {
+            StorageType::Table => archetype.push_component(&name, self, tick),
+            StorageType::SparseSet => sparse_sets
+                .get_mut(&name)
+                .and_then(|set| set.insert(entity, self, tick))
+                .ok_or_else(|| WorldError::TypeMismatch(name, type_name::<C>()))?,
+        }
+
+        Ok(())
+    }
+}
+
+macro_rules! impl_bundle_tuple {
+    ($($b:ident),+) => {
+        #[allow(non_snake_case)]
+        impl<$($b: Bundle),+> Bundle for ($($b,)+) {
+            fn components(&self, components: &mut Vec<BundleComponent>) {
+                let ($($b,)+) = self;
+                $($b.components(components);)+
+            }
+
+            fn push(
+                self,
+                entity: Entity,
+                archetype: &mut Archetype,
+                sparse_sets: &mut SparseSets,
+                tick: Tick,
+            ) -> WorldResult<()> {
+                let ($($b,)+) = self;
+                $($b.push(entity, archetype, sparse_sets, tick)?;)+
+                Ok(())
+            }
+        }
+    };
+}
+
+impl_bundle_tuple!(A);
+impl_bundle_tuple!(A, B);
+impl_bundle_tuple!(A, B, C);
+impl_bundle_tuple!(A, B, C, D);
+impl_bundle_tuple!(A, B, C, D, E);
+impl_bundle_tuple!(A, B, C, D, E, F);
+impl_bundle_tuple!(A, B, C, D, E, F, G);
+impl_bundle_tuple!(A, B, C, D, E, F, G, H);
+impl_bundle_tuple!(A, B, C, D, E, F, G, H, I);
+impl_bundle_tuple!(A, B, C, D, E, F, G, H, I, J);
+impl_bundle_tuple!(A, B, C, D, E, F, G, H, I, J, K);
+impl_bundle_tuple!(A, B, C, D, E, F, G, H, I, J, K, L);
+
+// Finds or creates the archetype holding exactly the table components, sorting them on the way.
+// The sparse sets of the other components are created as well.
+fn bundle_archetype(
+    archetypes: &mut Archetypes,
+    sparse_sets: &mut SparseSets,
+    components: &mut [BundleComponent],
+) -> WorldResult<ArchetypeId> {
+    components.sort_by(|a, b| str::cmp(&a.name, &b.name));
+
+    if let Some(pair) = components
+        .windows(2)
+        .find(|pair| pair[0].name == pair[1].name)
+    {
+        return Err(WorldError::DuplicateComponent(pair[0].name.clone()));
+    }
+
+    for component in components.iter() {
+        (component.register)(&component.name)?;
+    }
+
+    let tables = || {
+        components
+            .iter()
+            .filter(|c| c.storage == StorageType::Table)
+    };
+    let names = tables().map(|c| c.name.clone()).collect();
+    let id = archetypes.get_or_insert(names, || tables().map(|c| (c.new_column)()).collect());
+
+    // Another type might have created the storage under the same name
+    let archetype = archetypes.get(id).unwrap();
+    for component in components.iter() {
+        let column = match component.storage {
+            StorageType::Table => archetype.column(&component.name).unwrap(),
+            StorageType::SparseSet => sparse_sets
+                .get_or_insert(&component.name, component.new_column)
+                .column(),
+        };
+        if column.item_type_id() != component.type_id {
+            return Err(WorldError::TypeMismatch(
+                component.name.clone(),
+                component.type_name,
+            ));
+        }
+    }
+
+    Ok(id)
+}
+
+impl World {
+    // Spawns an entity with all the components at once, moving it between archetypes only once
+    pub fn spawn_bundle<B: Bundle>(&mut self, bundle: B) -> WorldResult<Entity> {
+        let mut components = vec![];
+        bundle.components(&mut components);
+        let (archetypes, sparse_sets) = self.storages_mut();
+        let archetype = bundle_archetype(archetypes, sparse_sets, &mut components)?;
+
+        let entity = self.alloc_entity();
+        self.place_bundle(entity, archetype, bundle)?;
+        self.trigger_added(entity, &components);
+        Ok(entity)
+    }
+
+    // Spawns an entity for every bundle, the archetype is only looked up again when the
+    // components differ from the previous bundle. Stops at the first bundle that can't be
+    // spawned, the entities spawned before it stay.
+    pub fn spawn_batch<B: Bundle>(
+        &mut self,
+        bundles: impl IntoIterator<Item = B>,
+    ) -> WorldResult<Vec<Entity>> {
+        let bundles = bundles.into_iter();
+        let mut entities = Vec::with_capacity(bundles.size_hint().0);
+
+        let mut components = vec![];
+        let mut previous: Option<(Vec<Name>, ArchetypeId)> = None;
+
+        for bundle in bundles {
+            components.clear();
+            bundle.components(&mut components);
+
+            let archetype = match &previous {
+                Some((names, archetype))
+                    if names.len() == components.len()
+                        && names.iter().zip(&components).all(|(n, c)| *n == c.name) =>
+                {
+                    *archetype
+                }
+                _ => {
+                    let names = components.iter().map(|c| c.name.clone()).collect();
+                    let (archetypes, sparse_sets) = self.storages_mut();
+                    let archetype = bundle_archetype(archetypes, sparse_sets, &mut components)?;
+                    previous = Some((names, archetype));
+                    archetype
+                }
+            };
+
+            let entity = self.alloc_entity();
+            self.place_bundle(entity, archetype, bundle)?;
+            self.trigger_added(entity, &components);
+            entities.push(entity);
+        }
+
+        Ok(entities)
+    }
+
+    fn trigger_added(&mut self, entity: Entity, components: &[BundleComponent]) {
+        for event in [ComponentEvent::Add, ComponentEvent::Insert] {
+            for component in components {
+                self.trigger(event, entity, &component.name);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::world::{
+        archetype::EMPTY_ARCHETYPE,
+        transform::{GlobalTransform, Transform},
+    };
+
+    use super::*;
+
+    #[derive(Component, Clone, Copy, Debug, PartialEq)]
+    struct Velocity(f32);
+
+    #[derive(Bundle)]
+    struct Body {
+        transform: Transform,
+        global: GlobalTransform,
+        velocity: Velocity,
+    }
+
+    #[test]
+    fn test_spawn_bundle() {
+        let mut world = World::new();
+
+        let a = world
+            .spawn_bundle((Transform::IDENTITY, Velocity(1.), Name::new("A")))
+            .unwrap();
+        // The same components in a different order and nested
+        let b = world
+            .spawn_bundle(((Velocity(2.), Name::new("B")), Transform::IDENTITY))
+            .unwrap();
+        let c = world
+            .spawn_bundle(Body {
+                transform: Transform::IDENTITY,
+                global: GlobalTransform::default(),
+                velocity: Velocity(3.),
+            })
+            .unwrap();
+
+        assert_eq!(world.get::<Velocity>(a), Ok(&Velocity(1.)));
+        assert_eq!(world.get::<Velocity>(b), Ok(&Velocity(2.)));
+        assert_eq!(world.get::<Velocity>(c), Ok(&Velocity(3.)));
+        assert!(world.has_component(a, Name::new("A")));
+        assert!(world.get::<GlobalTransform>(c).is_ok());
+
+        let count = world.query::<(&Transform, &Velocity)>().count();
+        assert_eq!(count, 3);
+
+        // Nothing is spawned when the bundle is rejected
+        assert_eq!(
+            world.spawn_bundle((Velocity(1.), Velocity(2.))),
+            Err(WorldError::DuplicateComponent(Velocity::component_name()))
+        );
+        assert_eq!(world.spawn(), Entity::new(0, 3));
+    }
+
+    #[test]
+    fn test_spawn_batch() {
+        let mut world = World::new();
+        let entities = world
+            .spawn_batch((0..100).map(|i| (Velocity(i as f32), Transform::IDENTITY)))
+            .unwrap();
+        assert_eq!(entities.len(), 100);
+        assert_eq!(world.get::<Velocity>(entities[42]), Ok(&Velocity(42.)));
+
+        // Labels make every bundle end up in an archetype of its own
+        let labelled = world
+            .spawn_batch((0..3).map(|i| (Velocity(0.), Name::new(format!("{i}")))))
+            .unwrap();
+        assert!(world.has_component(labelled[2], Name::new("2")));
+        assert!(!world.has_component(labelled[2], Name::new("1")));
+
+        assert_eq!(world.query::<&Velocity>().count(), 103);
+    }
+
+    #[test]
+    fn test_push_mismatch() {
+        #[derive(Component)]
+        #[component(storage = "sparse")]
+        struct Marker;
+
+        let mut world = World::new();
+        let entity = world.spawn();
+        let (archetypes, sparse_sets) = world.storages_mut();
+        let name = Marker::component_name();
+        sparse_sets.get_or_insert(&name, Column::new::<u32>);
+
+        let archetype = archetypes.get_mut(EMPTY_ARCHETYPE).unwrap();
+        assert_eq!(
+            Marker.push(entity, archetype, sparse_sets, Tick::default()),
+            Err(WorldError::TypeMismatch(name, type_name::<Marker>()))
+        );
+    }
+}
@@ -0,0 +1,353 @@
+use std::{
+    marker::PhantomData,
+    ops::{Deref, DerefMut},
+};
+
+use super::{
+    archetype::{Archetype, ArchetypeRow},
+    component::Component,
+    entity::Entity,
+    query::{may_have, Access, ColumnFetch, QueryFilter},
+    system::SystemParam,
+    world::World,
+};
+
+// A point in time of the world, advanced every time a system runs
+pub type Tick = u64;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ComponentTicks {
+    pub added: Tick,
+    pub changed: Tick,
+}
+
+impl ComponentTicks {
+    pub fn new(tick: Tick) -> Self {
+        Self {
+            added: tick,
+            changed: tick,
+        }
+    }
+
+    pub fn is_added(&self, last_run: Tick) -> bool {
+        self.added > last_run
+    }
+
+    pub fn is_changed(&self, last_run: Tick) -> bool {
+        self.changed > last_run
+    }
+}
+
+// The window of time a query or system looks at.
+// Changes made after `last_run` are new, changes made now are stamped with `this_run`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Ticks {
+    pub last_run: Tick,
+    pub this_run: Tick,
+}
+
+// Mutable borrow of a component that marks it changed once written through
+pub struct Mut<'w, T> {
+    value: &'w mut T,
+    ticks: &'w mut ComponentTicks,
+    this_run: Tick,
+}
+
+impl<'w, T> Mut<'w, T> {
+    pub(crate) fn new(value: &'w mut T, ticks: &'w mut ComponentTicks, this_run: Tick) -> Self {
+        Self {
+            value,
+            ticks,
+            this_run,
+        }
+    }
+
+    pub fn ticks(&self) -> ComponentTicks {
+        *self.ticks
+    }
+
+    // Writes without marking the component changed
+    pub fn bypass_change_detection(&mut self) -> &mut T {
+        self.value
+    }
+
+    pub fn into_inner(self) -> &'w mut T {
+        self.ticks.changed = self.this_run;
+        self.value
+    }
+}
+
+impl<T> Deref for Mut<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        self.value
+    }
+}
+
+impl<T> DerefMut for Mut<'_, T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.ticks.changed = self.this_run;
+        self.value
+    }
+}
+
+// Only matches components added since the last run
+pub struct Added<T>(PhantomData<T>);
+
+// Only matches components added or written to since the last run
+pub struct Changed<T>(PhantomData<T>);
+
+pub struct TickFetch<'w, T> {
+    column: ColumnFetch<'w, T>,
+    last_run: Tick,
+}
+
+impl<T: Component> QueryFilter for Added<T> {
+    type Fetch<'w> = TickFetch<'w, T>;
+
+    // The ticks are written alongside the component
+    fn update_access(access: &mut Access) {
+        access.add_filter_read(T::component_name());
+    }
+
+    fn matches_archetype(archetype: &Archetype) -> bool {
+        may_have::<T>(archetype)
+    }
+
+    unsafe fn fetch<'w>(
+        world: &'w World,
+        archetype: &'w Archetype,
+        ticks: Ticks,
+    ) -> Self::Fetch<'w> {
+        TickFetch {
+            column: ColumnFetch::new(world, archetype),
+            last_run: ticks.last_run,
+        }
+    }
+
+    unsafe fn filter_row(fetch: &mut Self::Fetch<'_>, entity: Entity, row: ArchetypeRow) -> bool {
+        fetch
+            .column
+            .row(entity, row)
+            .is_some_and(|row| fetch.column.ticks(row).is_added(fetch.last_run))
+    }
+}
+
+impl<T: Component> QueryFilter for Changed<T> {
+    type Fetch<'w> = TickFetch<'w, T>;
+
+    // The ticks are written alongside the component
+    fn update_access(access: &mut Access) {
+        access.add_filter_read(T::component_name());
+    }
+
+    fn matches_archetype(archetype: &Archetype) -> bool {
+        may_have::<T>(archetype)
+    }
+
+    unsafe fn fetch<'w>(
+        world: &'w World,
+        archetype: &'w Archetype,
+        ticks: Ticks,
+    ) -> Self::Fetch<'w> {
+        TickFetch {
+            column: ColumnFetch::new(world, archetype),
+            last_run: ticks.last_run,
+        }
+    }
+
+    unsafe fn filter_row(fetch: &mut Self::Fetch<'_>, entity: Entity, row: ArchetypeRow) -> bool {
+        fetch
+            .column
+            .row(entity, row)
+            .is_some_and(|row| fetch.column.ticks(row).is_changed(fetch.last_run))
+    }
+}
+
+// Entities that lost a `T`, either by removal or by being despawned, since the last run
+pub struct RemovedComponents<'w, T> {
+    removed: &'w [(Entity, Tick)],
+    last_run: Tick,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<'w, T: Component> RemovedComponents<'w, T> {
+    pub fn new(world: &'w World, last_run: Tick) -> Self {
+        Self {
+            removed: world.removed(&T::component_name()),
+            last_run,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = Entity> + '_ {
+        self.removed
+            .iter()
+            .filter(|(_, tick)| *tick > self.last_run)
+            .map(|(entity, _)| *entity)
+    }
+}
+
+impl<T: Component> SystemParam for RemovedComponents<'_, T> {
+    type Item<'w> = RemovedComponents<'w, T>;
+    type State = ();
+
+    fn init_state() -> Self::State {}
+
+    fn update_access(_: &mut Access) {}
+
+    unsafe fn fetch<'w>(_: &'w mut (), world: &'w World, ticks: Ticks) -> Self::Item<'w> {
+        RemovedComponents::new(world, ticks.last_run)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        utils::Name,
+        world::{
+            query::Query,
+            schedule::{Schedule, Stage},
+        },
+    };
+
+    use super::*;
+
+    #[derive(Clone, Copy)]
+    struct Sprite(u32);
+
+    impl Component for Sprite {
+        fn component_name() -> Name {
+            Name::new("Sprite")
+        }
+    }
+
+    struct Synced(Vec<(Entity, &'static str)>);
+
+    impl Component for Synced {
+        fn component_name() -> Name {
+            Name::new("Synced")
+        }
+    }
+
+    fn sync(
+        added: Query<Entity, Added<Sprite>>,
+        changed: Query<Entity, Changed<Sprite>>,
+        removed: RemovedComponents<Sprite>,
+        mut log: Query<&mut Synced>,
+    ) {
+        for (_, mut log) in log.iter_mut() {
+            log.0.extend(added.iter().map(|(e, _)| (e, "added")));
+            log.0.extend(changed.iter().map(|(e, _)| (e, "changed")));
+            log.0.extend(removed.iter().map(|e| (e, "removed")));
+        }
+    }
+
+    fn take_log(world: &mut World, logger: Entity) -> Vec<(Entity, &'static str)> {
+        let mut log = std::mem::take(&mut world.get_mut::<Synced>(logger).unwrap().0);
+        log.sort();
+        log
+    }
+
+    #[test]
+    fn test_change_detection() {
+        let mut world = World::new();
+
+        let logger = world.spawn();
+        world.add_component(logger, Synced(vec![])).unwrap();
+
+        let a = world.spawn();
+        world.add_component(a, Sprite(0)).unwrap();
+        let b = world.spawn();
+        world.add_component(b, Sprite(1)).unwrap();
+
+        let mut schedule = Schedule::new();
+        schedule.add_system(Stage::PostUpdate, sync);
+
+        schedule.run(&mut world).unwrap();
+        assert_eq!(
+            take_log(&mut world, logger),
+            vec![(a, "added"), (a, "changed"), (b, "added"), (b, "changed")]
+        );
+
+        schedule.run(&mut world).unwrap();
+        assert_eq!(take_log(&mut world, logger), vec![]);
+
+        world.get_mut::<Sprite>(b).unwrap().0 = 4;
+        // Reading through a mutable borrow is not a change
+        let _ = world.get_mut::<Sprite>(a).unwrap().0;
+
+        schedule.run(&mut world).unwrap();
+        assert_eq!(take_log(&mut world, logger), vec![(b, "changed")]);
+
+        world.remove::<Sprite>(a).unwrap();
+        world.despawn(b).unwrap();
+
+        schedule.run(&mut world).unwrap();
+        assert_eq!(
+            take_log(&mut world, logger),
+            vec![(a, "removed"), (b, "removed")]
+        );
+
+        schedule.run(&mut world).unwrap();
+        assert_eq!(take_log(&mut world, logger), vec![]);
+    }
+
+    #[test]
+    fn test_world_query_ticks() {
+        let mut world = World::new();
+
+        let a = world.spawn();
+        world.add_component(a, Sprite(0)).unwrap();
+        world.clear_trackers();
+
+        assert_eq!(world.query_filtered::<Entity, Changed<Sprite>>().count(), 0);
+
+        for (_, mut sprite) in world.query::<&mut Sprite>() {
+            sprite.0 += 1;
+        }
+
+        let changed: Vec<_> = world
+            .query_filtered::<Entity, Changed<Sprite>>()
+            .map(|(e, _)| e)
+            .collect();
+        assert_eq!(changed, vec![a]);
+
+        assert_eq!(
+            world
+                .query_filtered::<&mut Sprite, Changed<Sprite>>()
+                .count(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_despawn_keeps_ticks() {
+        let mut world = World::new();
+
+        let a = world.spawn();
+        world.add_component(a, Sprite(0)).unwrap();
+        world.clear_trackers();
+        let b = world.spawn();
+        world.add_component(b, Sprite(1)).unwrap();
+
+        world.despawn(a).unwrap();
+        let added: Vec<_> = world
+            .query_filtered::<Entity, Added<Sprite>>()
+            .map(|(e, _)| e)
+            .collect();
+        assert_eq!(added, vec![b]);
+
+        world.clear_trackers();
+        let c = world.spawn();
+        world.add_component(c, Sprite(2)).unwrap();
+        world.despawn(c).unwrap();
+        world.get_mut::<Sprite>(b).unwrap().0 = 3;
+        let changed: Vec<_> = world
+            .query_filtered::<Entity, Changed<Sprite>>()
+            .map(|(e, _)| e)
+            .collect();
+        assert_eq!(changed, vec![b]);
+    }
+}
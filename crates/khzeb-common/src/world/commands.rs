@@ -0,0 +1,186 @@
+use std::mem;
+
+use super::{
+    change::Ticks, component::Component, entity::Entity, query::Access, system::SystemParam,
+    world::World,
+};
+
+type Command = Box<dyn FnOnce(&mut World) + Send>;
+
+// Structural changes waiting for exclusive access to the world
+#[derive(Default)]
+pub struct CommandQueue {
+    commands: Vec<Command>,
+}
+
+impl CommandQueue {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, command: impl FnOnce(&mut World) + Send + 'static) {
+        self.commands.push(Box::new(command));
+    }
+
+    pub fn append(&mut self, other: &mut CommandQueue) {
+        self.commands.append(&mut other.commands);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.commands.is_empty()
+    }
+
+    pub fn apply(&mut self, world: &mut World) {
+        for command in mem::take(&mut self.commands) {
+            command(world);
+        }
+    }
+}
+
+// Records changes to apply at the next `World::flush`, which the schedule
+// performs after every stage. Commands against dead entities are ignored.
+pub struct Commands<'w> {
+    world: &'w World,
+    queue: CommandQueue,
+}
+
+impl<'w> Commands<'w> {
+    pub fn new(world: &'w World) -> Self {
+        Self {
+            world,
+            queue: CommandQueue::new(),
+        }
+    }
+
+    // The entity is usable in commands right away, but only exists after the flush
+    pub fn spawn(&mut self) -> Entity {
+        self.world.reserve_entity()
+    }
+
+    pub fn despawn(&mut self, entity: Entity) {
+        self.add(move |world| {
+            world.despawn(entity).ok();
+        });
+    }
+
+    pub fn insert<C: Component>(&mut self, entity: Entity, component: C) {
+        self.add(move |world| {
+            world.add_component(entity, component).ok();
+        });
+    }
+
+    pub fn remove<C: Component>(&mut self, entity: Entity) {
+        self.add(move |world| {
+            world.remove::<C>(entity).ok();
+        });
+    }
+
+    pub fn insert_resource<R: Send + Sync + 'static>(&mut self, resource: R) {
+        self.add(move |world| world.insert_resource(resource));
+    }
+
+    pub fn remove_resource<R: Send + Sync + 'static>(&mut self) {
+        self.add(move |world| {
+            world.remove_resource::<R>();
+        });
+    }
+
+    pub fn add(&mut self, command: impl FnOnce(&mut World) + Send + 'static) {
+        self.queue.push(command);
+    }
+}
+
+impl Drop for Commands<'_> {
+    fn drop(&mut self) {
+        if !self.queue.is_empty() {
+            self.world.queue_commands(&mut self.queue);
+        }
+    }
+}
+
+impl SystemParam for Commands<'_> {
+    type Item<'w> = Commands<'w>;
+    type State = ();
+
+    fn init_state() -> Self::State {}
+
+    fn update_access(_: &mut Access) {}
+
+    unsafe fn fetch<'w>(_: &'w mut (), world: &'w World, _: Ticks) -> Self::Item<'w> {
+        Commands::new(world)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        utils::Name,
+        world::{
+            query::Query,
+            schedule::{Schedule, Stage},
+        },
+    };
+
+    use super::*;
+
+    #[derive(Component)]
+    struct Health(i32);
+
+    fn reap(mut commands: Commands, query: Query<&Health>) {
+        for (entity, health) in query.iter() {
+            if health.0 <= 0 {
+                commands.despawn(entity);
+                let corpse = commands.spawn();
+                commands.insert(corpse, Name::new("Corpse"));
+            }
+        }
+    }
+
+    #[test]
+    fn test_deferred() {
+        let mut world = World::new();
+
+        let alive = world.spawn();
+        world.add_component(alive, Health(10)).unwrap();
+        let dead = world.spawn();
+        world.add_component(dead, Health(0)).unwrap();
+
+        let mut schedule = Schedule::new();
+        schedule.add_system(Stage::Update, reap);
+        schedule.run(&mut world).unwrap();
+
+        assert!(world.is_alive(alive));
+        assert!(!world.is_alive(dead));
+
+        let corpses: Vec<_> = world
+            .query_ref::<Entity>()
+            .filter(|(e, _)| world.has_component(*e, Name::new("Corpse")))
+            .collect();
+        assert_eq!(corpses.len(), 1);
+    }
+
+    #[test]
+    fn test_reserved() {
+        let mut world = World::new();
+
+        let mut commands = world.commands();
+        let a = commands.spawn();
+        let b = commands.spawn();
+        commands.insert(a, Health(1));
+        commands.insert(b, Health(2));
+        commands.remove::<Health>(b);
+        drop(commands);
+
+        assert!(!world.is_alive(a));
+
+        world.flush();
+
+        assert_eq!(world.get::<Health>(a).unwrap().0, 1);
+        assert!(world.is_alive(b));
+        assert!(world.get::<Health>(b).is_err());
+
+        let c = world.spawn();
+        assert_ne!(c.index(), a.index());
+        assert_ne!(c.index(), b.index());
+    }
+}
@@ -1,17 +1,226 @@
+use std::{
+    any::{type_name, TypeId},
+    collections::HashMap,
+    error::Error,
+    fmt,
+    sync::RwLock,
+};
+
 use crate::utils::Name;
 
-pub trait Component {
-    fn name(&self) -> Name;
+pub use khzeb_derive::Component;
+
+lazy_static::lazy_static! {
+    // The Rust type behind every registered component name
+    static ref COMPONENT_TYPES: RwLock<HashMap<Name, (TypeId, &'static str)>> = Default::default();
+}
+
+// Where the values of a component live
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum StorageType {
+    // In the columns of archetypes, the fastest to iterate
+    #[default]
+    Table,
+    // On the side, for components added and removed too often to move entities around for
+    SparseSet,
+}
+
+// Components are shared between systems running on different threads
+pub trait Component: Send + Sync + 'static {
+    fn storage_type() -> StorageType
+    where
+        Self: Sized,
+    {
+        StorageType::Table
+    }
+
+    // The name of the storage the component lives in when referred to by type
+    fn component_name() -> Name
+    where
+        Self: Sized;
+
+    // The name of this specific value, the same as `component_name` unless overridden
+    fn name(&self) -> Name
+    where
+        Self: Sized,
+    {
+        Self::component_name()
+    }
 }
 
 impl Component for () {
-    fn name(&self) -> Name {
+    fn component_name() -> Name {
         Name::new("()")
     }
 }
 
+// Names act as labels, each value is stored under itself
 impl Component for Name {
+    fn component_name() -> Name {
+        Name::new("Name")
+    }
+
     fn name(&self) -> Name {
         self.clone()
     }
 }
+
+// Two different Rust types claimed the same component name
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NameCollision {
+    pub name: Name,
+    pub existing: &'static str,
+    pub colliding: &'static str,
+}
+
+impl fmt::Display for NameCollision {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "component name {} is used by both {} and {}",
+            &*self.name, self.existing, self.colliding
+        )
+    }
+}
+
+impl Error for NameCollision {}
+
+// Claims the name for `T`, failing if another type claimed it before.
+// Done whenever storage for the name is created, and by `ComponentRegistry`.
+pub fn register_component_type<T: 'static>(name: &Name) -> Result<(), NameCollision> {
+    // Almost always claimed already
+    if let Some(&(id, _)) = COMPONENT_TYPES.read().unwrap().get(name) {
+        if id == TypeId::of::<T>() {
+            return Ok(());
+        }
+    }
+
+    let mut types = COMPONENT_TYPES.write().unwrap();
+    let (id, existing) = *types
+        .entry(name.clone())
+        .or_insert((TypeId::of::<T>(), type_name::<T>()));
+
+    if id == TypeId::of::<T>() {
+        Ok(())
+    } else {
+        Err(NameCollision {
+            name: name.clone(),
+            existing,
+            colliding: type_name::<T>(),
+        })
+    }
+}
+
+// The Rust type that claimed the name, if any did
+pub fn component_type_name(name: &Name) -> Option<&'static str> {
+    COMPONENT_TYPES
+        .read()
+        .unwrap()
+        .get(name)
+        .map(|(_, type_name)| *type_name)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::{Deserialize, Serialize};
+
+    use crate::world::{error::WorldError, registry::ComponentRegistry, world::World};
+
+    use super::*;
+
+    mod ui {
+        use super::*;
+
+        #[derive(Component)]
+        pub struct Position;
+
+        #[derive(Component)]
+        #[component(name = "Collided")]
+        pub struct First;
+
+        #[derive(Component)]
+        #[component(name = "Collided")]
+        pub struct Second;
+
+        #[derive(Component, Serialize, Deserialize)]
+        #[component(name = "Collided")]
+        pub struct Saved;
+    }
+
+    mod physics {
+        use super::*;
+
+        #[derive(Component)]
+        pub struct Position;
+    }
+
+    struct Manual;
+
+    impl Component for Manual {
+        fn component_name() -> Name {
+            Name::new("Collided")
+        }
+    }
+
+    #[test]
+    fn test_derived_names() {
+        let mut world = World::new();
+        let ui = ui::Position::component_name();
+        let physics = physics::Position::component_name();
+
+        assert_eq!(ui, "khzeb::world::component::tests::ui::Position");
+        assert_ne!(ui, physics);
+        let entity = world.spawn();
+        world.add_component(entity, ui::Position).unwrap();
+        world.add_component(entity, physics::Position).unwrap();
+        assert_eq!(component_type_name(&ui), Some(type_name::<ui::Position>()));
+    }
+
+    #[test]
+    fn test_name_collision() {
+        let mut world = World::new();
+        let a = world.spawn();
+        let b = world.spawn();
+        world.add_component(a, ui::First).unwrap();
+        world.add_component(b, ui::First).unwrap();
+
+        let collision = NameCollision {
+            name: Name::new("Collided"),
+            existing: type_name::<ui::First>(),
+            colliding: type_name::<ui::Second>(),
+        };
+        // Neither through a new archetype nor through an existing one
+        assert_eq!(
+            world.add_component(a, ui::Second),
+            Err(WorldError::NameCollision(collision.clone()))
+        );
+        assert_eq!(
+            world.spawn_bundle(ui::Second).map(|_| ()),
+            Err(WorldError::NameCollision(collision))
+        );
+        assert!(matches!(
+            world.spawn_bundle(Manual),
+            Err(WorldError::NameCollision(_))
+        ));
+        assert_eq!(ui::Second::component_name(), Manual::component_name());
+
+        // Registries check the names they take as well
+        assert!(ComponentRegistry::new().register::<ui::Saved>().is_err());
+    }
+
+    #[test]
+    fn test_register_collision() {
+        let name = Name::new("Registered");
+        register_component_type::<u8>(&name).unwrap();
+        register_component_type::<u8>(&name).unwrap();
+
+        assert_eq!(
+            register_component_type::<u16>(&name),
+            Err(NameCollision {
+                name,
+                existing: type_name::<u8>(),
+                colliding: type_name::<u16>(),
+            })
+        );
+    }
+}
@@ -0,0 +1,342 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::utils::Name;
+
+use super::{
+    entity::{Entity, EntityGen, EntityIdx},
+    registry::ComponentRegistry,
+    snapshot::{EntitySnapshot, SlotSnapshot, Snapshot, SnapshotError},
+    world::World,
+};
+
+// Component changes of a single entity, either spawned or alive on both sides
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct EntityDelta {
+    pub entity: Entity,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub added_labels: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub removed_labels: Vec<String>,
+    // Components added or changed, with their new value
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub inserted: BTreeMap<String, Value>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub removed: Vec<String>,
+}
+
+impl EntityDelta {
+    fn between(entity: Entity, from: &EntitySnapshot, to: &EntitySnapshot) -> Self {
+        let missing = |labels: &[String], from: &[String]| -> Vec<String> {
+            labels
+                .iter()
+                .filter(|label| !from.contains(label))
+                .cloned()
+                .collect()
+        };
+
+        Self {
+            entity,
+            added_labels: missing(&to.labels, &from.labels),
+            removed_labels: missing(&from.labels, &to.labels),
+            inserted: to
+                .components
+                .iter()
+                .filter(|(name, value)| from.components.get(*name) != Some(value))
+                .map(|(name, value)| (name.clone(), value.clone()))
+                .collect(),
+            removed: from
+                .components
+                .keys()
+                .filter(|name| !to.components.contains_key(*name))
+                .cloned()
+                .collect(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.added_labels.is_empty()
+            && self.removed_labels.is_empty()
+            && self.inserted.is_empty()
+            && self.removed.is_empty()
+    }
+}
+
+// The difference between two states of a world.
+// Only the slots that changed are mentioned, applying it to the older state yields the newer one.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct WorldDelta {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub despawned: Vec<Entity>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub spawned: Vec<Entity>,
+    // Slots that are vacant afterwards but went through a generation in between
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub vacant_generations: Vec<(EntityIdx, EntityGen)>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub entities: Vec<EntityDelta>,
+    pub free_list: Vec<EntityIdx>,
+}
+
+impl WorldDelta {
+    pub fn between(from: &Snapshot, to: &Snapshot) -> Self {
+        let mut delta = WorldDelta {
+            free_list: to.free_list.clone(),
+            ..Default::default()
+        };
+
+        let vacant = SlotSnapshot {
+            generation: 0,
+            entity: None,
+        };
+
+        for (index, new) in to.slots.iter().enumerate() {
+            let old = from.slots.get(index).unwrap_or(&vacant);
+            let index = index as EntityIdx;
+            let old_entity = Entity::new(old.generation, index);
+            let new_entity = Entity::new(new.generation, index);
+
+            match (&old.entity, &new.entity) {
+                (Some(before), Some(after)) if old.generation == new.generation => {
+                    let entity_delta = EntityDelta::between(new_entity, before, after);
+                    if !entity_delta.is_empty() {
+                        delta.entities.push(entity_delta);
+                    }
+                }
+                (before, after) => {
+                    if before.is_some() {
+                        delta.despawned.push(old_entity);
+                    }
+
+                    match after {
+                        Some(after) => {
+                            delta.spawned.push(new_entity);
+                            delta.entities.push(EntityDelta::between(
+                                new_entity,
+                                &EntitySnapshot::default(),
+                                after,
+                            ));
+                        }
+                        None if old.generation != new.generation
+                            || index as usize >= from.slots.len() =>
+                        {
+                            delta.vacant_generations.push((index, new.generation));
+                        }
+                        None => {}
+                    }
+                }
+            }
+        }
+
+        delta
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.despawned.is_empty()
+            && self.spawned.is_empty()
+            && self.vacant_generations.is_empty()
+            && self.entities.is_empty()
+    }
+
+    // Applies the delta to a world in the state it was computed from
+    pub fn apply(
+        &self,
+        world: &mut World,
+        registry: &ComponentRegistry,
+    ) -> Result<(), SnapshotError> {
+        // The parents and relations they leave behind are patched below
+        for &entity in &self.despawned {
+            world.despawn_unlinked(entity)?;
+        }
+
+        for &(index, generation) in &self.vacant_generations {
+            world.set_vacant_generation(index, generation)?;
+        }
+
+        for &entity in &self.spawned {
+            world.spawn_at(entity)?;
+        }
+
+        for entity_delta in &self.entities {
+            let entity = entity_delta.entity;
+
+            for label in &entity_delta.removed_labels {
+                world.remove_by_name(entity, &Name::new(label))?;
+            }
+
+            for name in &entity_delta.removed {
+                world.remove_by_name(entity, &Name::new(name))?;
+            }
+
+            for label in &entity_delta.added_labels {
+                world.add_component(entity, Name::new(label))?;
+            }
+
+            for (name, value) in &entity_delta.inserted {
+                let name = Name::new(name);
+                registry
+                    .get(&name)
+                    .ok_or(SnapshotError::Unregistered(name))?
+                    .insert(world, entity, value.clone())?;
+            }
+        }
+
+        world.set_free_list(self.free_list.clone());
+        Ok(())
+    }
+
+    pub fn to_text(&self) -> Result<String, SnapshotError> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    pub fn from_text(text: &str) -> Result<Self, SnapshotError> {
+        Ok(serde_json::from_str(text)?)
+    }
+
+    pub fn to_binary(&self) -> Result<Vec<u8>, SnapshotError> {
+        Ok(rmp_serde::to_vec_named(self)?)
+    }
+
+    pub fn from_binary(bytes: &[u8]) -> Result<Self, SnapshotError> {
+        Ok(rmp_serde::from_slice(bytes)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::Vec2;
+
+    use crate::world::{component::Component, error::WorldError, transform::Transform};
+
+    use super::*;
+
+    #[derive(Serialize, Deserialize)]
+    struct Score(u32);
+
+    impl Component for Score {
+        fn component_name() -> Name {
+            Name::new("Score")
+        }
+    }
+
+    fn registry() -> ComponentRegistry {
+        let mut registry = ComponentRegistry::new();
+        registry.register::<Score>().unwrap();
+        registry
+    }
+
+    #[test]
+    fn test_delta_roundtrip() {
+        let registry = registry();
+        let mut world = World::new();
+
+        let kept = world.spawn();
+        world.add_component(kept, Score(1)).unwrap();
+        world.add_component(kept, Name::new("Red")).unwrap();
+        let doomed = world.spawn();
+        world.add_component(doomed, Score(2)).unwrap();
+        let untouched = world.spawn();
+        world
+            .add_component(untouched, Transform::default())
+            .unwrap();
+
+        let before = Snapshot::capture(&world, &registry).unwrap();
+
+        world.get_mut::<Score>(kept).unwrap().0 = 10;
+        world.remove_by_name(kept, &Name::new("Red")).unwrap();
+        world.add_component(kept, Name::new("Blue")).unwrap();
+        world
+            .add_component(kept, Transform::from_translation(Vec2::X))
+            .unwrap();
+        world.despawn(doomed).unwrap();
+
+        // Reuses the slot of `doomed`
+        let reborn = world.spawn();
+        world.add_component(reborn, Score(3)).unwrap();
+        // Goes through a generation without being around at the end
+        let fleeting = world.spawn();
+        world.despawn(fleeting).unwrap();
+
+        let after = Snapshot::capture(&world, &registry).unwrap();
+
+        let delta = WorldDelta::between(&before, &after);
+        assert_eq!(delta.despawned, vec![doomed]);
+        assert_eq!(delta.spawned, vec![reborn]);
+        assert_eq!(delta.vacant_generations, vec![(fleeting.index(), 0)]);
+        // `untouched` is left out entirely
+        assert_eq!(delta.entities.len(), 2);
+
+        let encoded = delta.to_binary().unwrap();
+        let decoded = WorldDelta::from_binary(&encoded).unwrap();
+        assert_eq!(
+            WorldDelta::from_text(&delta.to_text().unwrap()).unwrap(),
+            delta
+        );
+
+        let mut replica = before.restore(&registry).unwrap();
+        decoded.apply(&mut replica, &registry).unwrap();
+
+        assert_eq!(Snapshot::capture(&replica, &registry).unwrap(), after);
+        assert_eq!(replica.spawn(), world.spawn());
+
+        assert!(WorldDelta::between(&after, &after).is_empty());
+    }
+
+    #[test]
+    fn test_delta_hierarchy() {
+        let registry = registry();
+        let mut world = World::new();
+
+        let root = world.spawn();
+        let child = world.spawn();
+        let grandchild = world.spawn();
+        let sibling = world.spawn();
+        world.set_parent(child, root).unwrap();
+        world.set_parent(grandchild, child).unwrap();
+        world.set_parent(sibling, root).unwrap();
+
+        let before = Snapshot::capture(&world, &registry).unwrap();
+        let mut replica = before.restore(&registry).unwrap();
+
+        // Leaves `root` with one child and `grandchild` without a parent
+        world.despawn(child).unwrap();
+
+        let after = Snapshot::capture(&world, &registry).unwrap();
+        WorldDelta::between(&before, &after)
+            .apply(&mut replica, &registry)
+            .unwrap();
+
+        assert_eq!(Snapshot::capture(&replica, &registry).unwrap(), after);
+        assert_eq!(replica.children(root), [sibling]);
+        assert_eq!(replica.parent(grandchild), None);
+
+        let before = after;
+        world.despawn_recursive(root).unwrap();
+        let after = Snapshot::capture(&world, &registry).unwrap();
+        WorldDelta::between(&before, &after)
+            .apply(&mut replica, &registry)
+            .unwrap();
+
+        assert_eq!(Snapshot::capture(&replica, &registry).unwrap(), after);
+    }
+
+    #[test]
+    fn test_apply_mismatch() {
+        let registry = registry();
+        let mut world = World::new();
+        let a = world.spawn();
+
+        let before = Snapshot::capture(&world, &registry).unwrap();
+        world.despawn(a).unwrap();
+        let after = Snapshot::capture(&world, &registry).unwrap();
+
+        let delta = WorldDelta::between(&before, &after);
+        delta.apply(&mut world, &registry).unwrap_err();
+        assert_eq!(
+            delta.apply(&mut World::new(), &registry),
+            Err(SnapshotError::World(WorldError::InvalidEntity(a)))
+        );
+    }
+}
@@ -1,9 +1,12 @@
 use core::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
 
 pub type EntityGen = u32;
 pub type EntityIdx = u32;
 
-#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub struct Entity(u64);
 
 impl Entity {
@@ -33,3 +36,13 @@ impl fmt::Display for Entity {
         ))
     }
 }
+
+// Parses the `42v3` shorthand of index 42 at generation 3
+impl FromStr for Entity {
+    type Err = std::num::ParseIntError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (index, generation) = s.split_once('v').unwrap_or((s, "0"));
+        Ok(Entity::new(generation.parse()?, index.parse()?))
+    }
+}
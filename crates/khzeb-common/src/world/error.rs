@@ -0,0 +1,58 @@
+use std::{error::Error, fmt};
+
+use crate::utils::Name;
+
+use super::{component::NameCollision, entity::Entity};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum WorldError {
+    // The index was never handed out by this world
+    InvalidEntity(Entity),
+    // The entity was despawned, possibly with its slot reused since
+    StaleEntity(Entity),
+    MissingComponent(Entity, Name),
+    MissingResource(Name),
+    // The name is already used by a component of a different type
+    TypeMismatch(Name, &'static str),
+    // A bundle contains more than one component with the name
+    DuplicateComponent(Name),
+    // The entity does not match the query it was looked up in
+    QueryMismatch(Entity),
+    // Parenting the first entity to the second would make it its own ancestor
+    HierarchyCycle(Entity, Entity),
+    NameCollision(NameCollision),
+}
+
+impl fmt::Display for WorldError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WorldError::InvalidEntity(entity) => write!(f, "{entity} does not exist"),
+            WorldError::StaleEntity(entity) => write!(f, "{entity} is no longer alive"),
+            WorldError::MissingComponent(entity, name) => {
+                write!(f, "{entity} has no component {}", &**name)
+            }
+            WorldError::MissingResource(name) => write!(f, "resource {} does not exist", &**name),
+            WorldError::TypeMismatch(name, type_name) => {
+                write!(f, "component {} is not a {type_name}", &**name)
+            }
+            WorldError::DuplicateComponent(name) => {
+                write!(f, "component {} appears more than once", &**name)
+            }
+            WorldError::QueryMismatch(entity) => write!(f, "{entity} does not match the query"),
+            WorldError::HierarchyCycle(child, parent) => {
+                write!(f, "{child} is an ancestor of {parent}")
+            }
+            WorldError::NameCollision(collision) => collision.fmt(f),
+        }
+    }
+}
+
+impl Error for WorldError {}
+
+impl From<NameCollision> for WorldError {
+    fn from(collision: NameCollision) -> Self {
+        WorldError::NameCollision(collision)
+    }
+}
+
+pub type WorldResult<T> = Result<T, WorldError>;
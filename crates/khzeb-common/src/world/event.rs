@@ -0,0 +1,300 @@
+use super::{
+    change::Ticks,
+    query::Access,
+    resource::{Res, ResMut},
+    system::SystemParam,
+    world::World,
+};
+
+// Events are identified by the order they were sent in
+pub type EventId = usize;
+
+// Double-buffered queue of events of one type.
+// Every `update` drops the older buffer, so an event lives for two frames and
+// every system gets to see it once regardless of where it runs relative to the sender.
+pub struct Events<E> {
+    previous: Vec<(EventId, E)>,
+    current: Vec<(EventId, E)>,
+    event_count: EventId,
+}
+
+impl<E> Default for Events<E> {
+    fn default() -> Self {
+        Self {
+            previous: vec![],
+            current: vec![],
+            event_count: 0,
+        }
+    }
+}
+
+impl<E> Events<E> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn send(&mut self, event: E) -> EventId {
+        let id = self.event_count;
+        self.current.push((id, event));
+        self.event_count += 1;
+        id
+    }
+
+    pub fn send_batch(&mut self, events: impl IntoIterator<Item = E>) {
+        for event in events {
+            self.send(event);
+        }
+    }
+
+    // Swaps the buffers, dropping the events sent before the previous update
+    pub fn update(&mut self) {
+        self.previous = std::mem::take(&mut self.current);
+    }
+
+    pub fn clear(&mut self) {
+        self.previous.clear();
+        self.current.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.previous.len() + self.current.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // The id the next sent event is going to get
+    pub fn event_count(&self) -> EventId {
+        self.event_count
+    }
+
+    // All retained events, oldest first
+    pub fn iter(&self) -> impl Iterator<Item = (EventId, &E)> {
+        self.previous
+            .iter()
+            .chain(self.current.iter())
+            .map(|(id, event)| (*id, event))
+    }
+
+    // Retained events sent at or after the cursor, oldest first
+    pub fn iter_since(&self, cursor: EventId) -> impl Iterator<Item = (EventId, &E)> {
+        let skip = |events: &[(EventId, E)]| {
+            events
+                .first()
+                .map_or(0, |(first, _)| cursor.saturating_sub(*first))
+                .min(events.len())
+        };
+
+        self.previous[skip(&self.previous)..]
+            .iter()
+            .chain(self.current[skip(&self.current)..].iter())
+            .map(|(id, event)| (*id, event))
+    }
+}
+
+// Updaters of every event type added to a world, run once per frame
+#[derive(Default)]
+pub(crate) struct EventUpdates(Vec<fn(&mut World)>);
+
+impl World {
+    // Makes the events of type `E` expire on their own at the end of every frame
+    pub fn add_event<E: Send + Sync + 'static>(&mut self) {
+        if self.contains_resource::<Events<E>>() {
+            return;
+        }
+
+        self.insert_resource(Events::<E>::new());
+
+        if !self.contains_resource::<EventUpdates>() {
+            self.insert_resource(EventUpdates::default());
+        }
+        self.resource_mut::<EventUpdates>()
+            .unwrap()
+            .0
+            .push(|world| {
+                if let Ok(mut events) = world.resource_mut::<Events<E>>() {
+                    events.update();
+                }
+            });
+    }
+
+    pub fn send_event<E: Send + Sync + 'static>(&mut self, event: E) -> Option<EventId> {
+        self.resource_mut::<Events<E>>()
+            .ok()
+            .map(|mut events| events.send(event))
+    }
+
+    pub(crate) fn update_events(&mut self) {
+        let Some(updates) = self.remove_resource::<EventUpdates>() else {
+            return;
+        };
+
+        for update in &updates.0 {
+            update(self);
+        }
+
+        self.insert_resource(updates);
+    }
+}
+
+// Sends events of type `E`, which has to be added to the world beforehand
+pub struct EventWriter<'w, E: Send + Sync + 'static> {
+    events: ResMut<'w, Events<E>>,
+}
+
+impl<E: Send + Sync + 'static> EventWriter<'_, E> {
+    pub fn send(&mut self, event: E) -> EventId {
+        self.events.send(event)
+    }
+
+    pub fn send_batch(&mut self, events: impl IntoIterator<Item = E>) {
+        self.events.send_batch(events);
+    }
+}
+
+impl<E: Send + Sync + 'static> SystemParam for EventWriter<'_, E> {
+    type Item<'w> = EventWriter<'w, E>;
+    type State = ();
+
+    fn init_state() -> Self::State {}
+
+    fn update_access(access: &mut Access) {
+        ResMut::<Events<E>>::update_access(access);
+    }
+
+    unsafe fn fetch<'w>(_: &'w mut (), world: &'w World, ticks: Ticks) -> Self::Item<'w> {
+        EventWriter {
+            events: ResMut::new(world, ticks),
+        }
+    }
+}
+
+// Reads events of type `E`, each reading system keeps its own cursor
+pub struct EventReader<'w, E: Send + Sync + 'static> {
+    events: Res<'w, Events<E>>,
+    cursor: &'w mut EventId,
+}
+
+impl<E: Send + Sync + 'static> EventReader<'_, E> {
+    // Events not yet seen by this reader, oldest first
+    pub fn read(&mut self) -> impl Iterator<Item = &E> {
+        let cursor = std::mem::replace(self.cursor, self.events.event_count());
+        self.events.iter_since(cursor).map(|(_, event)| event)
+    }
+
+    pub fn len(&self) -> usize {
+        self.events.iter_since(*self.cursor).count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // Marks every pending event as seen
+    pub fn clear(&mut self) {
+        *self.cursor = self.events.event_count();
+    }
+}
+
+impl<E: Send + Sync + 'static> SystemParam for EventReader<'_, E> {
+    type Item<'w> = EventReader<'w, E>;
+    type State = EventId;
+
+    fn init_state() -> Self::State {
+        0
+    }
+
+    fn update_access(access: &mut Access) {
+        Res::<Events<E>>::update_access(access);
+    }
+
+    unsafe fn fetch<'w>(state: &'w mut EventId, world: &'w World, ticks: Ticks) -> Self::Item<'w> {
+        EventReader {
+            events: Res::new(world, ticks),
+            cursor: state,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use crate::world::{
+        schedule::{Schedule, Stage},
+        system::Local,
+    };
+
+    use super::*;
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    struct Hit(u32);
+
+    static SEEN: Mutex<Vec<(&'static str, u32)>> = Mutex::new(vec![]);
+
+    fn send(mut writer: EventWriter<Hit>, mut frame: Local<u32>) {
+        *frame += 1;
+        if *frame <= 2 {
+            writer.send(Hit(*frame));
+        }
+    }
+
+    fn early(mut reader: EventReader<Hit>) {
+        let hits: Vec<_> = reader.read().map(|hit| ("early", hit.0)).collect();
+        SEEN.lock().unwrap().extend(hits);
+    }
+
+    fn late(mut reader: EventReader<Hit>) {
+        let hits: Vec<_> = reader.read().map(|hit| ("late", hit.0)).collect();
+        SEEN.lock().unwrap().extend(hits);
+    }
+
+    #[test]
+    fn test_events() {
+        let mut world = World::new();
+        world.add_event::<Hit>();
+
+        // Readers before the writer see the events on the next frame
+        let mut schedule = Schedule::new();
+        schedule.add_system(Stage::PreUpdate, early);
+        schedule.add_system(Stage::Update, send);
+        schedule.add_system(Stage::PostUpdate, late);
+
+        for _ in 0..4 {
+            schedule.run(&mut world).unwrap();
+        }
+
+        let mut seen = std::mem::take(&mut *SEEN.lock().unwrap());
+        seen.sort();
+        assert_eq!(
+            seen,
+            vec![("early", 1), ("early", 2), ("late", 1), ("late", 2)]
+        );
+        assert!(world.resource::<Events<Hit>>().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_expiry() {
+        let mut events = Events::new();
+        events.send(Hit(0));
+        events.update();
+        events.send(Hit(1));
+
+        assert_eq!(
+            events.iter_since(1).map(|(_, e)| *e).collect::<Vec<_>>(),
+            vec![Hit(1)]
+        );
+
+        events.update();
+        assert_eq!(
+            events.iter().map(|(_, e)| *e).collect::<Vec<_>>(),
+            vec![Hit(1)]
+        );
+        // A reader that fell behind only misses what expired
+        assert_eq!(events.iter_since(0).count(), 1);
+
+        events.update();
+        assert!(events.is_empty());
+    }
+}
@@ -0,0 +1,209 @@
+use serde::{Deserialize, Serialize};
+
+use super::{
+    commands::Commands,
+    component::Component,
+    entity::Entity,
+    error::{WorldError, WorldResult},
+    reflect::Reflect,
+    world::World,
+};
+
+// The entity this one is attached to.
+// Only made by `set_parent`, which keeps the `Children` of the parent in sync.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, Component, Reflect)]
+pub struct Parent(Entity);
+
+impl Parent {
+    pub fn get(&self) -> Entity {
+        self.0
+    }
+}
+
+// The entities attached to this one, in the order they were attached
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize, Component, Reflect)]
+pub struct Children(Vec<Entity>);
+
+impl Children {
+    pub fn as_slice(&self) -> &[Entity] {
+        &self.0
+    }
+}
+
+impl World {
+    pub fn parent(&self, entity: Entity) -> Option<Entity> {
+        self.get::<Parent>(entity).ok().map(|parent| parent.0)
+    }
+
+    pub fn children(&self, entity: Entity) -> &[Entity] {
+        self.get::<Children>(entity)
+            .map(|children| children.0.as_slice())
+            .unwrap_or_default()
+    }
+
+    // Attaches the child to the parent, detaching it from the previous one
+    pub fn set_parent(&mut self, child: Entity, parent: Entity) -> WorldResult<()> {
+        self.location(child)?;
+        self.location(parent)?;
+
+        let mut ancestor = Some(parent);
+        while let Some(current) = ancestor {
+            if current == child {
+                return Err(WorldError::HierarchyCycle(child, parent));
+            }
+            ancestor = self.parent(current);
+        }
+
+        self.detach(child);
+        self.add_component(child, Parent(parent))?;
+
+        match self.get_mut::<Children>(parent) {
+            Ok(mut children) => children.0.push(child),
+            Err(_) => self.add_component(parent, Children(vec![child]))?,
+        }
+
+        Ok(())
+    }
+
+    // Detaches the entity from its parent, making it a root
+    pub fn remove_parent(&mut self, child: Entity) -> WorldResult<()> {
+        self.location(child)?;
+        self.detach(child);
+        self.remove::<Parent>(child).map(|_| ())
+    }
+
+    // Despawns the entity together with all of its descendants
+    pub fn despawn_recursive(&mut self, entity: Entity) -> WorldResult<()> {
+        self.location(entity)?;
+        self.detach(entity);
+
+        let mut stack = vec![entity];
+        while let Some(current) = stack.pop() {
+            stack.extend_from_slice(self.children(current));
+            // Children despawned on their own leave stale entries behind
+            self.despawn(current).ok();
+        }
+
+        Ok(())
+    }
+
+    // Cuts a despawning entity out of the hierarchy, its children become roots
+    pub(crate) fn unlink_hierarchy(&mut self, entity: Entity) {
+        self.detach(entity);
+        for child in self.children(entity).to_vec() {
+            self.remove::<Parent>(child).ok();
+        }
+    }
+
+    // Takes the entity out of the children of its parent, keeping its `Parent`
+    fn detach(&mut self, child: Entity) {
+        let Some(parent) = self.parent(child) else {
+            return;
+        };
+
+        let now_empty = match self.get_mut::<Children>(parent) {
+            Ok(mut children) => {
+                children.0.retain(|&e| e != child);
+                children.0.is_empty()
+            }
+            Err(_) => false,
+        };
+
+        if now_empty {
+            self.remove::<Children>(parent).ok();
+        }
+    }
+}
+
+impl Commands<'_> {
+    pub fn set_parent(&mut self, child: Entity, parent: Entity) {
+        self.add(move |world| {
+            world.set_parent(child, parent).ok();
+        });
+    }
+
+    pub fn remove_parent(&mut self, child: Entity) {
+        self.add(move |world| {
+            world.remove_parent(child).ok();
+        });
+    }
+
+    pub fn despawn_recursive(&mut self, entity: Entity) {
+        self.add(move |world| {
+            world.despawn_recursive(entity).ok();
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reparent() {
+        let mut world = World::new();
+
+        let a = world.spawn();
+        let b = world.spawn();
+        let child = world.spawn();
+
+        world.set_parent(child, a).unwrap();
+        assert_eq!(world.parent(child), Some(a));
+        assert_eq!(world.children(a), &[child]);
+
+        world.set_parent(child, b).unwrap();
+        assert_eq!(world.parent(child), Some(b));
+        assert_eq!(world.children(b), &[child]);
+        assert!(world.children(a).is_empty());
+        assert!(!world.has_component(a, Children::component_name()));
+
+        assert_eq!(
+            world.set_parent(b, child),
+            Err(WorldError::HierarchyCycle(b, child))
+        );
+
+        world.remove_parent(child).unwrap();
+        assert_eq!(world.parent(child), None);
+        assert!(world.children(b).is_empty());
+    }
+
+    #[test]
+    fn test_despawn_recursive() {
+        let mut world = World::new();
+
+        let root = world.spawn();
+        let parent = world.spawn();
+        let a = world.spawn();
+        let b = world.spawn();
+        let sibling = world.spawn();
+
+        world.set_parent(parent, root).unwrap();
+        world.set_parent(sibling, root).unwrap();
+        world.set_parent(a, parent).unwrap();
+        world.set_parent(b, parent).unwrap();
+
+        world.despawn_recursive(parent).unwrap();
+
+        for entity in [parent, a, b] {
+            assert!(!world.is_alive(entity));
+        }
+        assert!(world.is_alive(sibling));
+        assert_eq!(world.children(root), &[sibling]);
+    }
+
+    #[test]
+    fn test_despawn_orphans() {
+        let mut world = World::new();
+
+        let root = world.spawn();
+        let parent = world.spawn();
+        let child = world.spawn();
+        world.set_parent(parent, root).unwrap();
+        world.set_parent(child, parent).unwrap();
+
+        world.despawn(parent).unwrap();
+        assert_eq!(world.parent(child), None);
+        assert!(world.children(root).is_empty());
+        assert!(!world.has_component(root, Children::component_name()));
+    }
+}
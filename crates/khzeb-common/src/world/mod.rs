@@ -1,3 +1,24 @@
+pub mod archetype;
+pub mod bundle;
+pub mod change;
+pub mod commands;
 pub mod component;
+pub mod delta;
 pub mod entity;
+pub mod error;
+pub mod event;
+pub mod hierarchy;
+pub mod observer;
+pub mod prefab;
+pub mod query;
+pub mod reflect;
+pub mod registry;
+pub mod relation;
+pub mod resource;
+pub mod schedule;
+pub mod snapshot;
+pub mod sparse_set;
+pub mod system;
+pub mod transform;
+#[allow(clippy::module_inception)]
 pub mod world;
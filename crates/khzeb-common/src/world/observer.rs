@@ -0,0 +1,243 @@
+use std::{collections::HashMap, sync::Arc};
+
+use crate::utils::Name;
+
+use super::{component::Component, entity::Entity, error::WorldResult, world::World};
+
+type Hook = Arc<dyn Fn(&mut World, Entity) + Send + Sync>;
+type Observer = Arc<dyn Fn(&mut World, &Trigger) + Send + Sync>;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ComponentEvent {
+    // The entity did not have the component before
+    Add,
+    // The component was added or overwritten
+    Insert,
+    // The component is about to be removed, either on its own or with the entity
+    Remove,
+}
+
+// What an entity observer is told about
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Trigger {
+    pub event: ComponentEvent,
+    pub entity: Entity,
+    pub component: Name,
+}
+
+#[derive(Clone, Default)]
+struct ComponentHooks {
+    on_add: Vec<Hook>,
+    on_insert: Vec<Hook>,
+    on_remove: Vec<Hook>,
+}
+
+impl ComponentHooks {
+    fn get(&self, event: ComponentEvent) -> &[Hook] {
+        match event {
+            ComponentEvent::Add => &self.on_add,
+            ComponentEvent::Insert => &self.on_insert,
+            ComponentEvent::Remove => &self.on_remove,
+        }
+    }
+}
+
+// Callbacks run right as components come and go, with full access to the world
+#[derive(Default)]
+pub(crate) struct Observers {
+    // By the name of the component
+    hooks: HashMap<Name, ComponentHooks>,
+    entities: HashMap<Entity, Vec<Observer>>,
+}
+
+impl Observers {
+    pub(crate) fn is_empty(&self) -> bool {
+        self.hooks.is_empty() && self.entities.is_empty()
+    }
+
+    pub(crate) fn forget(&mut self, entity: Entity) {
+        self.entities.remove(&entity);
+    }
+}
+
+impl World {
+    // Runs when the component is added to an entity that did not have it
+    pub fn on_add<C: Component>(
+        &mut self,
+        hook: impl Fn(&mut World, Entity) + Send + Sync + 'static,
+    ) {
+        self.hooks_mut::<C>().on_add.push(Arc::new(hook));
+    }
+
+    // Runs whenever the component is added or overwritten, after `on_add`
+    pub fn on_insert<C: Component>(
+        &mut self,
+        hook: impl Fn(&mut World, Entity) + Send + Sync + 'static,
+    ) {
+        self.hooks_mut::<C>().on_insert.push(Arc::new(hook));
+    }
+
+    // Runs before the component is removed or the entity despawned, the component is still there
+    pub fn on_remove<C: Component>(
+        &mut self,
+        hook: impl Fn(&mut World, Entity) + Send + Sync + 'static,
+    ) {
+        self.hooks_mut::<C>().on_remove.push(Arc::new(hook));
+    }
+
+    fn hooks_mut<C: Component>(&mut self) -> &mut ComponentHooks {
+        self.observers_mut()
+            .hooks
+            .entry(C::component_name())
+            .or_default()
+    }
+
+    // Runs on every component event of the entity, until it is despawned
+    pub fn observe(
+        &mut self,
+        entity: Entity,
+        observer: impl Fn(&mut World, &Trigger) + Send + Sync + 'static,
+    ) -> WorldResult<()> {
+        self.location(entity)?;
+        self.observers_mut()
+            .entities
+            .entry(entity)
+            .or_default()
+            .push(Arc::new(observer));
+        Ok(())
+    }
+
+    // Runs the hooks of the component, then the observers of the entity
+    pub(crate) fn trigger(&mut self, event: ComponentEvent, entity: Entity, component: &Name) {
+        let observers = self.observers();
+        if observers.is_empty() {
+            return;
+        }
+
+        // Cloned out, the callbacks are free to register more of them
+        let hooks = observers
+            .hooks
+            .get(component)
+            .map(|hooks| hooks.get(event).to_vec())
+            .unwrap_or_default();
+        let entity_observers = observers.entities.get(&entity).cloned().unwrap_or_default();
+
+        for hook in hooks {
+            hook(self, entity);
+        }
+
+        if entity_observers.is_empty() {
+            return;
+        }
+
+        let trigger = Trigger {
+            event,
+            entity,
+            component: component.clone(),
+        };
+        for observer in entity_observers {
+            observer(self, &trigger);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use crate::world::error::WorldError;
+
+    use super::*;
+
+    #[derive(Component)]
+    struct Sprite;
+
+    #[derive(Component, Debug, PartialEq)]
+    struct BatchInstance(usize);
+
+    #[derive(Default)]
+    struct Batch {
+        free: Vec<usize>,
+        len: usize,
+    }
+
+    #[test]
+    fn test_hooks() {
+        let mut world = World::new();
+        world.insert_resource(Batch::default());
+
+        world.on_add::<Sprite>(|world, entity| {
+            let mut batch = world.resource_mut::<Batch>().unwrap();
+            let slot = match batch.free.pop() {
+                Some(slot) => slot,
+                None => {
+                    batch.len += 1;
+                    batch.len - 1
+                }
+            };
+            world.add_component(entity, BatchInstance(slot)).unwrap();
+        });
+        world.on_remove::<Sprite>(|world, entity| {
+            let BatchInstance(slot) = world.remove::<BatchInstance>(entity).unwrap();
+            world.resource_mut::<Batch>().unwrap().free.push(slot);
+        });
+
+        let a = world.spawn();
+        world.add_component(a, Sprite).unwrap();
+        let b = world.spawn_bundle((Sprite, Name::new("B"))).unwrap();
+        assert_eq!(world.get::<BatchInstance>(a), Ok(&BatchInstance(0)));
+        assert_eq!(world.get::<BatchInstance>(b), Ok(&BatchInstance(1)));
+
+        // Overwriting is not adding
+        world.add_component(a, Sprite).unwrap();
+        assert_eq!(world.resource::<Batch>().unwrap().len, 2);
+
+        world.remove::<Sprite>(a).unwrap();
+        assert!(world.get::<BatchInstance>(a).is_err());
+        world.despawn(b).unwrap();
+        assert_eq!(world.resource::<Batch>().unwrap().free, vec![0, 1]);
+
+        let c = world.spawn_bundle(Sprite).unwrap();
+        assert_eq!(world.get::<BatchInstance>(c), Ok(&BatchInstance(1)));
+    }
+
+    #[test]
+    fn test_entity_observers() {
+        let mut world = World::new();
+        let log = Arc::new(Mutex::new(vec![]));
+
+        let a = world.spawn();
+        let b = world.spawn();
+        let observed = log.clone();
+        world
+            .observe(a, move |_, trigger| {
+                observed
+                    .lock()
+                    .unwrap()
+                    .push((trigger.event, trigger.component.to_string()));
+            })
+            .unwrap();
+
+        world.add_component(a, Sprite).unwrap();
+        world.add_component(a, Sprite).unwrap();
+        world.add_component(b, Sprite).unwrap();
+        world.despawn(a).unwrap();
+
+        let sprite = Sprite::component_name().to_string();
+        assert_eq!(
+            *log.lock().unwrap(),
+            vec![
+                (ComponentEvent::Add, sprite.clone()),
+                (ComponentEvent::Insert, sprite.clone()),
+                (ComponentEvent::Insert, sprite.clone()),
+                (ComponentEvent::Remove, sprite),
+            ]
+        );
+
+        // The slot of `a` is reused without its observers
+        let c = world.spawn();
+        world.add_component(c, Sprite).unwrap();
+        assert_eq!(log.lock().unwrap().len(), 4);
+        assert_eq!(world.observe(a, |_, _| {}), Err(WorldError::StaleEntity(a)));
+    }
+}
@@ -0,0 +1,400 @@
+use std::{
+    collections::{BTreeMap, HashSet},
+    error::Error,
+    fmt, fs, io,
+    path::Path,
+};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::utils::Name;
+
+use super::{
+    entity::Entity, error::WorldError, registry::ComponentRegistry, snapshot::SnapshotError,
+    world::World,
+};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum PrefabError {
+    Unknown(String),
+    // The prefab ends up extending itself
+    InheritanceCycle(String),
+    Unregistered(Name),
+    Io(String),
+    // Malformed prefab file, or component data that does not fit the type
+    Format(String),
+    World(WorldError),
+}
+
+impl fmt::Display for PrefabError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PrefabError::Unknown(name) => write!(f, "prefab {name} does not exist"),
+            PrefabError::InheritanceCycle(name) => write!(f, "prefab {name} extends itself"),
+            PrefabError::Unregistered(name) => {
+                write!(f, "component {} is not registered", &**name)
+            }
+            PrefabError::Io(reason) => write!(f, "failed to read prefabs: {reason}"),
+            PrefabError::Format(reason) => write!(f, "malformed prefab: {reason}"),
+            PrefabError::World(err) => err.fmt(f),
+        }
+    }
+}
+
+impl Error for PrefabError {}
+
+impl From<WorldError> for PrefabError {
+    fn from(value: WorldError) -> Self {
+        PrefabError::World(value)
+    }
+}
+
+impl From<SnapshotError> for PrefabError {
+    fn from(value: SnapshotError) -> Self {
+        match value {
+            SnapshotError::Unregistered(name) => PrefabError::Unregistered(name),
+            SnapshotError::World(err) => PrefabError::World(err),
+            err => PrefabError::Format(err.to_string()),
+        }
+    }
+}
+
+impl From<serde_json::Error> for PrefabError {
+    fn from(value: serde_json::Error) -> Self {
+        PrefabError::Format(value.to_string())
+    }
+}
+
+impl From<io::Error> for PrefabError {
+    fn from(value: io::Error) -> Self {
+        PrefabError::Io(value.to_string())
+    }
+}
+
+// A template for entities. Components go by their registered name, or by the name
+// without the module path, with their values in the same form as in snapshots.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Prefab {
+    // The prefab this one starts from, its values are overridden by the ones here
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub extends: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub labels: Vec<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub components: BTreeMap<String, Value>,
+}
+
+impl Prefab {
+    // Starts an instance of another prefab, to be overridden before instantiating it
+    pub fn extending(name: impl ToString) -> Self {
+        Self {
+            extends: Some(name.to_string()),
+            ..Default::default()
+        }
+    }
+
+    pub fn with_label(mut self, label: impl ToString) -> Self {
+        self.labels.push(label.to_string());
+        self
+    }
+
+    pub fn with_component(mut self, name: impl ToString, value: Value) -> Self {
+        self.components.insert(name.to_string(), value);
+        self
+    }
+
+    // Lays `other` over this prefab. Objects are merged field by field,
+    // so an override only has to mention the fields it changes.
+    fn merge(&mut self, other: &Prefab) {
+        for label in &other.labels {
+            if !self.labels.contains(label) {
+                self.labels.push(label.clone());
+            }
+        }
+
+        for (name, value) in &other.components {
+            match self.components.get_mut(name) {
+                Some(base) => merge_value(base, value),
+                None => {
+                    self.components.insert(name.clone(), value.clone());
+                }
+            }
+        }
+    }
+}
+
+fn merge_value(base: &mut Value, over: &Value) {
+    match (base, over) {
+        (Value::Object(base), Value::Object(over)) => {
+            for (key, value) in over {
+                match base.get_mut(key) {
+                    Some(base) => merge_value(base, value),
+                    None => {
+                        base.insert(key.clone(), value.clone());
+                    }
+                }
+            }
+        }
+        (base, over) => *base = over.clone(),
+    }
+}
+
+// Prefabs by name, usually loaded from a file mapping names to prefabs
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Prefabs {
+    prefabs: BTreeMap<String, Prefab>,
+}
+
+impl Prefabs {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, PrefabError> {
+        Self::from_text(&fs::read_to_string(path)?)
+    }
+
+    pub fn from_text(text: &str) -> Result<Self, PrefabError> {
+        Ok(serde_json::from_str(text)?)
+    }
+
+    pub fn to_text(&self) -> Result<String, PrefabError> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    // Adds the prefabs of another file, replacing the ones with the same name
+    pub fn extend(&mut self, other: Prefabs) {
+        self.prefabs.extend(other.prefabs);
+    }
+
+    pub fn insert(&mut self, name: impl ToString, prefab: Prefab) {
+        self.prefabs.insert(name.to_string(), prefab);
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Prefab> {
+        self.prefabs.get(name)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Prefab)> {
+        self.prefabs
+            .iter()
+            .map(|(name, prefab)| (name.as_str(), prefab))
+    }
+
+    // Flattens the chain of prefabs `prefab` extends into a single one extending nothing.
+    // Component names are spelled as registered so values from different levels meet.
+    pub fn resolve(
+        &self,
+        prefab: &Prefab,
+        registry: &ComponentRegistry,
+    ) -> Result<Prefab, PrefabError> {
+        let mut chain = vec![prefab];
+        let mut visited = HashSet::new();
+
+        while let Some(parent) = &chain.last().unwrap().extends {
+            if !visited.insert(parent) {
+                return Err(PrefabError::InheritanceCycle(parent.clone()));
+            }
+
+            let parent = self
+                .get(parent)
+                .ok_or_else(|| PrefabError::Unknown(parent.clone()))?;
+            chain.push(parent);
+        }
+
+        let mut resolved = Prefab::default();
+        for prefab in chain.into_iter().rev() {
+            let mut prefab = prefab.clone();
+            prefab.components = prefab
+                .components
+                .into_iter()
+                .map(|(name, value)| match registry.find(&Name::new(&name)) {
+                    Some(registration) => (registration.name().to_string(), value),
+                    None => (name, value),
+                })
+                .collect();
+
+            resolved.merge(&prefab);
+        }
+
+        Ok(resolved)
+    }
+
+    pub fn instantiate(
+        &self,
+        name: &str,
+        world: &mut World,
+        registry: &ComponentRegistry,
+    ) -> Result<Entity, PrefabError> {
+        self.instantiate_prefab(&Prefab::extending(name), world, registry)
+    }
+
+    // Spawns an entity from a prefab that is not part of the collection,
+    // like an instance overriding some values with `Prefab::extending`.
+    // Nothing is left behind when a component can't be added.
+    pub fn instantiate_prefab(
+        &self,
+        prefab: &Prefab,
+        world: &mut World,
+        registry: &ComponentRegistry,
+    ) -> Result<Entity, PrefabError> {
+        let prefab = self.resolve(prefab, registry)?;
+        let entity = world.spawn();
+
+        match Self::populate(&prefab, entity, world, registry) {
+            Ok(()) => Ok(entity),
+            Err(err) => {
+                world.despawn(entity)?;
+                Err(err)
+            }
+        }
+    }
+
+    fn populate(
+        prefab: &Prefab,
+        entity: Entity,
+        world: &mut World,
+        registry: &ComponentRegistry,
+    ) -> Result<(), PrefabError> {
+        for label in &prefab.labels {
+            world.add_component(entity, Name::new(label))?;
+        }
+
+        for (name, value) in &prefab.components {
+            let name = Name::new(name);
+            registry
+                .get(&name)
+                .ok_or(PrefabError::Unregistered(name))?
+                .insert(world, entity, value.clone())?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::world::{component::Component, transform::Transform};
+
+    use super::*;
+
+    #[derive(Component, Debug, PartialEq, Serialize, Deserialize)]
+    struct Health(u32);
+
+    #[derive(Component, Debug, PartialEq, Serialize, Deserialize)]
+    struct Sprite {
+        index: u32,
+        tint: [f32; 4],
+    }
+
+    const PREFABS: &str = r#"{
+        "creature": {
+            "labels": ["Creature"],
+            "components": {
+                "Health": 10,
+                "Sprite": { "index": 0, "tint": [1.0, 1.0, 1.0, 1.0] }
+            }
+        },
+        "goblin": {
+            "extends": "creature",
+            "labels": ["Goblin"],
+            "components": {
+                "Sprite": { "index": 3 },
+                "Transform": { "translation": [0.0, 0.0], "rotation": 0.0, "scale": [1.0, 1.0] }
+            }
+        }
+    }"#;
+
+    fn registry() -> ComponentRegistry {
+        let mut registry = ComponentRegistry::new();
+        registry
+            .register::<Health>()
+            .unwrap()
+            .register::<Sprite>()
+            .unwrap();
+        registry
+    }
+
+    #[test]
+    fn test_instantiate() {
+        let path = std::env::temp_dir().join(format!(
+            "khzeb_test_instantiate_{}.json",
+            std::process::id()
+        ));
+        fs::write(&path, PREFABS).unwrap();
+        let prefabs = Prefabs::load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        let registry = registry();
+        let mut world = World::new();
+
+        let goblin = prefabs
+            .instantiate("goblin", &mut world, &registry)
+            .unwrap();
+        assert_eq!(world.get::<Health>(goblin), Ok(&Health(10)));
+        assert_eq!(
+            world.get::<Sprite>(goblin),
+            Ok(&Sprite {
+                index: 3,
+                tint: [1.; 4]
+            })
+        );
+        assert!(world.has_component(goblin, Name::new("Creature")));
+        assert!(world.has_component(goblin, Name::new("Goblin")));
+        assert_eq!(world.get::<Transform>(goblin), Ok(&Transform::IDENTITY));
+
+        let instance = Prefab::extending("goblin")
+            .with_label("Boss")
+            .with_component("Health", json!(50))
+            .with_component("Sprite", json!({ "tint": [1.0, 0.0, 0.0, 1.0] }));
+        let boss = prefabs
+            .instantiate_prefab(&instance, &mut world, &registry)
+            .unwrap();
+        assert_eq!(world.get::<Health>(boss), Ok(&Health(50)));
+        assert_eq!(
+            world.get::<Sprite>(boss),
+            Ok(&Sprite {
+                index: 3,
+                tint: [1., 0., 0., 1.]
+            })
+        );
+        assert!(world.has_component(boss, Name::new("Boss")));
+
+        assert_eq!(Prefabs::from_text(&prefabs.to_text().unwrap()), Ok(prefabs));
+    }
+
+    #[test]
+    fn test_errors() {
+        let registry = registry();
+        let mut world = World::new();
+
+        let mut prefabs = Prefabs::new();
+        prefabs.insert("a", Prefab::extending("b"));
+        prefabs.insert("b", Prefab::extending("a"));
+        prefabs.insert("orphan", Prefab::extending("missing"));
+        prefabs.insert(
+            "broken",
+            Prefab::default()
+                .with_label("Broken")
+                .with_component("Health", json!("full")),
+        );
+
+        assert_eq!(
+            prefabs.instantiate("a", &mut world, &registry),
+            Err(PrefabError::InheritanceCycle("a".to_string()))
+        );
+        assert_eq!(
+            prefabs.instantiate("orphan", &mut world, &registry),
+            Err(PrefabError::Unknown("missing".to_string()))
+        );
+        assert!(matches!(
+            prefabs.instantiate("broken", &mut world, &registry),
+            Err(PrefabError::Format(_))
+        ));
+        assert_eq!(world.query::<Entity>().count(), 0);
+    }
+}
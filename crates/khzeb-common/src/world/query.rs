@@ -0,0 +1,802 @@
+use std::{cell::UnsafeCell, marker::PhantomData};
+
+use crate::utils::Name;
+
+use super::{
+    archetype::{Archetype, ArchetypeRow, Column},
+    change::{ComponentTicks, Mut, Tick, Ticks},
+    component::{Component, StorageType},
+    entity::Entity,
+    error::{WorldError, WorldResult},
+    sparse_set::SparseSet,
+    world::World,
+};
+
+// The components and resources a query or system reads and writes
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Access {
+    reads: Vec<Name>,
+    writes: Vec<Name>,
+    resource_reads: Vec<Name>,
+    resource_writes: Vec<Name>,
+}
+
+impl Access {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_read(&mut self, name: Name) {
+        assert!(
+            !self.writes.contains(&name),
+            "Component {} is read and written at the same time",
+            &*name
+        );
+
+        if !self.reads.contains(&name) {
+            self.reads.push(name);
+        }
+    }
+
+    pub fn add_write(&mut self, name: Name) {
+        assert!(
+            !self.writes.contains(&name) && !self.reads.contains(&name),
+            "Component {} is aliased by a mutable borrow",
+            &*name
+        );
+
+        self.writes.push(name);
+    }
+
+    // Reads by filters are covered by writes of the query they belong to
+    pub fn add_filter_read(&mut self, name: Name) {
+        if !self.writes.contains(&name) {
+            self.add_read(name);
+        }
+    }
+
+    pub fn add_resource_read(&mut self, name: Name) {
+        assert!(
+            !self.resource_writes.contains(&name),
+            "Resource {} is read and written at the same time",
+            &*name
+        );
+
+        if !self.resource_reads.contains(&name) {
+            self.resource_reads.push(name);
+        }
+    }
+
+    pub fn add_resource_write(&mut self, name: Name) {
+        assert!(
+            !self.resource_writes.contains(&name) && !self.resource_reads.contains(&name),
+            "Resource {} is aliased by a mutable borrow",
+            &*name
+        );
+
+        self.resource_writes.push(name);
+    }
+
+    pub fn merge(&mut self, other: &Access) {
+        for name in &other.reads {
+            self.add_read(name.clone());
+        }
+        for name in &other.writes {
+            self.add_write(name.clone());
+        }
+        for name in &other.resource_reads {
+            self.add_resource_read(name.clone());
+        }
+        for name in &other.resource_writes {
+            self.add_resource_write(name.clone());
+        }
+    }
+
+    pub fn reads(&self) -> &[Name] {
+        &self.reads
+    }
+
+    pub fn writes(&self) -> &[Name] {
+        &self.writes
+    }
+
+    pub fn resource_reads(&self) -> &[Name] {
+        &self.resource_reads
+    }
+
+    pub fn resource_writes(&self) -> &[Name] {
+        &self.resource_writes
+    }
+
+    // Whether both can be granted at the same time
+    pub fn is_compatible(&self, other: &Access) -> bool {
+        let conflicts = |writes: &[Name], reads: &[Name], other_writes: &[Name]| {
+            writes
+                .iter()
+                .any(|name| reads.contains(name) || other_writes.contains(name))
+        };
+
+        !conflicts(&self.writes, &other.reads, &other.writes)
+            && !conflicts(&other.writes, &self.reads, &self.writes)
+            && !conflicts(
+                &self.resource_writes,
+                &other.resource_reads,
+                &other.resource_writes,
+            )
+            && !conflicts(
+                &other.resource_writes,
+                &self.resource_reads,
+                &self.resource_writes,
+            )
+    }
+}
+
+/// Data fetched from every entity a query matches.
+///
+/// # Safety
+///
+/// `update_access` must report every component `item` borrows, with mutable
+/// borrows reported as writes.
+pub unsafe trait WorldQuery {
+    type Item<'w>;
+    type Fetch<'w>;
+
+    fn update_access(access: &mut Access);
+
+    // Components in sparse sets can't rule out an archetype, only single entities
+    fn matches_archetype(archetype: &Archetype) -> bool;
+
+    /// # Safety
+    ///
+    /// The archetype must belong to the world and match the query.
+    unsafe fn fetch<'w>(
+        world: &'w World,
+        archetype: &'w Archetype,
+        ticks: Ticks,
+    ) -> Self::Fetch<'w>;
+
+    /// # Safety
+    ///
+    /// The row must be in bounds and hold the entity.
+    unsafe fn matches_entity(fetch: &Self::Fetch<'_>, entity: Entity, row: ArchetypeRow) -> bool;
+
+    /// # Safety
+    ///
+    /// The row must be in bounds, hold the entity and match the query, and no other
+    /// live borrow may conflict with the access.
+    unsafe fn item<'w>(
+        fetch: &mut Self::Fetch<'w>,
+        entity: Entity,
+        row: ArchetypeRow,
+    ) -> Self::Item<'w>;
+}
+
+/// # Safety
+///
+/// The query must only perform reads.
+pub unsafe trait ReadOnlyWorldQuery: WorldQuery {}
+
+// Narrows down the entities a query visits without fetching data
+pub trait QueryFilter {
+    type Fetch<'w>;
+
+    fn update_access(access: &mut Access);
+
+    fn matches_archetype(archetype: &Archetype) -> bool;
+
+    /// # Safety
+    ///
+    /// The archetype must belong to the world and match the filter.
+    unsafe fn fetch<'w>(
+        world: &'w World,
+        archetype: &'w Archetype,
+        ticks: Ticks,
+    ) -> Self::Fetch<'w>;
+
+    /// # Safety
+    ///
+    /// The row must be in bounds and hold the entity.
+    unsafe fn filter_row(fetch: &mut Self::Fetch<'_>, entity: Entity, row: ArchetypeRow) -> bool;
+}
+
+pub struct With<T>(PhantomData<T>);
+
+pub struct Without<T>(PhantomData<T>);
+
+// Whether the archetype might hold entities with the component
+pub(crate) fn may_have<T: Component>(archetype: &Archetype) -> bool {
+    T::storage_type() == StorageType::SparseSet
+        || archetype
+            .column(&T::component_name())
+            .is_some_and(Column::is::<T>)
+}
+
+// The values of a component in either kind of storage
+pub struct ColumnFetch<'w, T> {
+    values: &'w [UnsafeCell<T>],
+    ticks: &'w [UnsafeCell<ComponentTicks>],
+    // For sparse components, `None` if no entity ever had one
+    sparse: Option<&'w SparseSet>,
+}
+
+impl<'w, T: Component> ColumnFetch<'w, T> {
+    /// # Safety
+    ///
+    /// Table components have to be in the archetype.
+    pub(crate) unsafe fn new(world: &'w World, archetype: &'w Archetype) -> Self {
+        let name = T::component_name();
+        let (column, sparse) = match T::storage_type() {
+            StorageType::Table => (archetype.column(&name), None),
+            StorageType::SparseSet => {
+                let set = world.sparse_sets().get(&name);
+                (set.map(SparseSet::column), set)
+            }
+        };
+
+        // Another type stored under the name matches no entity
+        let column = column.filter(|column| column.is::<T>());
+        let sparse = sparse.filter(|_| column.is_some());
+
+        Self {
+            values: column
+                .and_then(|column| column.as_cells::<T>())
+                .unwrap_or_default(),
+            ticks: column.map(Column::tick_cells).unwrap_or_default(),
+            sparse,
+        }
+    }
+
+    // Where the component of the entity at the archetype row sits, if it has one
+    pub(crate) fn row(&self, entity: Entity, row: ArchetypeRow) -> Option<ArchetypeRow> {
+        match T::storage_type() {
+            StorageType::Table => Some(row),
+            StorageType::SparseSet => self.sparse.and_then(|set| set.row(entity)),
+        }
+    }
+
+    /// # Safety
+    ///
+    /// The row has to come from `row` and no mutable borrow of the ticks may be live.
+    pub(crate) unsafe fn ticks(&self, row: ArchetypeRow) -> ComponentTicks {
+        *self.ticks[row].get()
+    }
+}
+
+unsafe impl WorldQuery for Entity {
+    type Item<'w> = Entity;
+    type Fetch<'w> = ();
+
+    fn update_access(_: &mut Access) {}
+
+    fn matches_archetype(_: &Archetype) -> bool {
+        true
+    }
+
+    unsafe fn fetch<'w>(_: &'w World, _: &'w Archetype, _: Ticks) -> Self::Fetch<'w> {}
+
+    unsafe fn matches_entity(_: &Self::Fetch<'_>, _: Entity, _: ArchetypeRow) -> bool {
+        true
+    }
+
+    unsafe fn item<'w>(_: &mut Self::Fetch<'w>, entity: Entity, _: ArchetypeRow) -> Self::Item<'w> {
+        entity
+    }
+}
+
+unsafe impl ReadOnlyWorldQuery for Entity {}
+
+unsafe impl<T: Component> WorldQuery for &T {
+    type Item<'w> = &'w T;
+    type Fetch<'w> = ColumnFetch<'w, T>;
+
+    fn update_access(access: &mut Access) {
+        access.add_read(T::component_name());
+    }
+
+    fn matches_archetype(archetype: &Archetype) -> bool {
+        may_have::<T>(archetype)
+    }
+
+    unsafe fn fetch<'w>(world: &'w World, archetype: &'w Archetype, _: Ticks) -> Self::Fetch<'w> {
+        ColumnFetch::new(world, archetype)
+    }
+
+    unsafe fn matches_entity(fetch: &Self::Fetch<'_>, entity: Entity, row: ArchetypeRow) -> bool {
+        fetch.row(entity, row).is_some()
+    }
+
+    unsafe fn item<'w>(
+        fetch: &mut Self::Fetch<'w>,
+        entity: Entity,
+        row: ArchetypeRow,
+    ) -> Self::Item<'w> {
+        let row = fetch.row(entity, row).unwrap();
+        &*fetch.values[row].get()
+    }
+}
+
+unsafe impl<T: Component> ReadOnlyWorldQuery for &T {}
+
+pub struct MutFetch<'w, T> {
+    column: ColumnFetch<'w, T>,
+    this_run: Tick,
+}
+
+unsafe impl<T: Component> WorldQuery for &mut T {
+    type Item<'w> = Mut<'w, T>;
+    type Fetch<'w> = MutFetch<'w, T>;
+
+    fn update_access(access: &mut Access) {
+        access.add_write(T::component_name());
+    }
+
+    fn matches_archetype(archetype: &Archetype) -> bool {
+        may_have::<T>(archetype)
+    }
+
+    unsafe fn fetch<'w>(
+        world: &'w World,
+        archetype: &'w Archetype,
+        ticks: Ticks,
+    ) -> Self::Fetch<'w> {
+        MutFetch {
+            column: ColumnFetch::new(world, archetype),
+            this_run: ticks.this_run,
+        }
+    }
+
+    unsafe fn matches_entity(fetch: &Self::Fetch<'_>, entity: Entity, row: ArchetypeRow) -> bool {
+        fetch.column.row(entity, row).is_some()
+    }
+
+    unsafe fn item<'w>(
+        fetch: &mut Self::Fetch<'w>,
+        entity: Entity,
+        row: ArchetypeRow,
+    ) -> Self::Item<'w> {
+        let row = fetch.column.row(entity, row).unwrap();
+        Mut::new(
+            &mut *fetch.column.values[row].get(),
+            &mut *fetch.column.ticks[row].get(),
+            fetch.this_run,
+        )
+    }
+}
+
+unsafe impl<Q: WorldQuery> WorldQuery for Option<Q> {
+    type Item<'w> = Option<Q::Item<'w>>;
+    type Fetch<'w> = Option<Q::Fetch<'w>>;
+
+    fn update_access(access: &mut Access) {
+        Q::update_access(access);
+    }
+
+    fn matches_archetype(_: &Archetype) -> bool {
+        true
+    }
+
+    unsafe fn fetch<'w>(
+        world: &'w World,
+        archetype: &'w Archetype,
+        ticks: Ticks,
+    ) -> Self::Fetch<'w> {
+        Some(archetype)
+            .filter(|a| Q::matches_archetype(a))
+            .map(|a| Q::fetch(world, a, ticks))
+    }
+
+    unsafe fn matches_entity(_: &Self::Fetch<'_>, _: Entity, _: ArchetypeRow) -> bool {
+        true
+    }
+
+    unsafe fn item<'w>(
+        fetch: &mut Self::Fetch<'w>,
+        entity: Entity,
+        row: ArchetypeRow,
+    ) -> Self::Item<'w> {
+        match fetch {
+            Some(fetch) if Q::matches_entity(fetch, entity, row) => {
+                Some(Q::item(fetch, entity, row))
+            }
+            _ => None,
+        }
+    }
+}
+
+unsafe impl<Q: ReadOnlyWorldQuery> ReadOnlyWorldQuery for Option<Q> {}
+
+impl QueryFilter for () {
+    type Fetch<'w> = ();
+
+    fn update_access(_: &mut Access) {}
+
+    fn matches_archetype(_: &Archetype) -> bool {
+        true
+    }
+
+    unsafe fn fetch<'w>(_: &'w World, _: &'w Archetype, _: Ticks) -> Self::Fetch<'w> {}
+
+    unsafe fn filter_row(_: &mut Self::Fetch<'_>, _: Entity, _: ArchetypeRow) -> bool {
+        true
+    }
+}
+
+// The sparse set of a component, `None` for table components and sets that don't exist yet
+fn sparse_set<T: Component>(world: &World) -> Option<&SparseSet> {
+    match T::storage_type() {
+        StorageType::Table => None,
+        StorageType::SparseSet => world
+            .sparse_sets()
+            .get(&T::component_name())
+            .filter(|set| set.column().is::<T>()),
+    }
+}
+
+impl<T: Component> QueryFilter for With<T> {
+    type Fetch<'w> = Option<&'w SparseSet>;
+
+    fn update_access(_: &mut Access) {}
+
+    fn matches_archetype(archetype: &Archetype) -> bool {
+        may_have::<T>(archetype)
+    }
+
+    unsafe fn fetch<'w>(world: &'w World, _: &'w Archetype, _: Ticks) -> Self::Fetch<'w> {
+        sparse_set::<T>(world)
+    }
+
+    unsafe fn filter_row(fetch: &mut Self::Fetch<'_>, entity: Entity, _: ArchetypeRow) -> bool {
+        match T::storage_type() {
+            StorageType::Table => true,
+            StorageType::SparseSet => fetch.is_some_and(|set| set.contains(entity)),
+        }
+    }
+}
+
+impl<T: Component> QueryFilter for Without<T> {
+    type Fetch<'w> = Option<&'w SparseSet>;
+
+    fn update_access(_: &mut Access) {}
+
+    fn matches_archetype(archetype: &Archetype) -> bool {
+        T::storage_type() == StorageType::SparseSet || !may_have::<T>(archetype)
+    }
+
+    unsafe fn fetch<'w>(world: &'w World, _: &'w Archetype, _: Ticks) -> Self::Fetch<'w> {
+        sparse_set::<T>(world)
+    }
+
+    unsafe fn filter_row(fetch: &mut Self::Fetch<'_>, entity: Entity, _: ArchetypeRow) -> bool {
+        !fetch.is_some_and(|set| set.contains(entity))
+    }
+}
+
+macro_rules! impl_query_tuple {
+    ($($q:ident),+) => {
+        #[allow(non_snake_case)]
+        unsafe impl<$($q: WorldQuery),+> WorldQuery for ($($q,)+) {
+            type Item<'w> = ($($q::Item<'w>,)+);
+            type Fetch<'w> = ($($q::Fetch<'w>,)+);
+
+            fn update_access(access: &mut Access) {
+                $($q::update_access(access);)+
+            }
+
+            fn matches_archetype(archetype: &Archetype) -> bool {
+                $($q::matches_archetype(archetype))&&+
+            }
+
+            unsafe fn fetch<'w>(
+                world: &'w World,
+                archetype: &'w Archetype,
+                ticks: Ticks,
+            ) -> Self::Fetch<'w> {
+                ($($q::fetch(world, archetype, ticks),)+)
+            }
+
+            unsafe fn matches_entity(
+                fetch: &Self::Fetch<'_>,
+                entity: Entity,
+                row: ArchetypeRow,
+            ) -> bool {
+                let ($($q,)+) = fetch;
+                $($q::matches_entity($q, entity, row))&&+
+            }
+
+            unsafe fn item<'w>(
+                fetch: &mut Self::Fetch<'w>,
+                entity: Entity,
+                row: ArchetypeRow,
+            ) -> Self::Item<'w> {
+                let ($($q,)+) = fetch;
+                ($($q::item($q, entity, row),)+)
+            }
+        }
+
+        unsafe impl<$($q: ReadOnlyWorldQuery),+> ReadOnlyWorldQuery for ($($q,)+) {}
+
+        #[allow(non_snake_case)]
+        impl<$($q: QueryFilter),+> QueryFilter for ($($q,)+) {
+            type Fetch<'w> = ($($q::Fetch<'w>,)+);
+
+            fn update_access(access: &mut Access) {
+                $($q::update_access(access);)+
+            }
+
+            fn matches_archetype(archetype: &Archetype) -> bool {
+                $($q::matches_archetype(archetype))&&+
+            }
+
+            unsafe fn fetch<'w>(
+                world: &'w World,
+                archetype: &'w Archetype,
+                ticks: Ticks,
+            ) -> Self::Fetch<'w> {
+                ($($q::fetch(world, archetype, ticks),)+)
+            }
+
+            unsafe fn filter_row(
+                fetch: &mut Self::Fetch<'_>,
+                entity: Entity,
+                row: ArchetypeRow,
+            ) -> bool {
+                let ($($q,)+) = fetch;
+                $($q::filter_row($q, entity, row))&&+
+            }
+        }
+    };
+}
+
+impl_query_tuple!(A);
+impl_query_tuple!(A, B);
+impl_query_tuple!(A, B, C);
+impl_query_tuple!(A, B, C, D);
+impl_query_tuple!(A, B, C, D, E);
+impl_query_tuple!(A, B, C, D, E, F);
+impl_query_tuple!(A, B, C, D, E, F, G);
+impl_query_tuple!(A, B, C, D, E, F, G, H);
+
+pub struct QueryIter<'w, Q: WorldQuery, F: QueryFilter> {
+    world: &'w World,
+    archetypes: std::slice::Iter<'w, Archetype>,
+    ticks: Ticks,
+    current: Option<(&'w Archetype, Q::Fetch<'w>, F::Fetch<'w>)>,
+    row: ArchetypeRow,
+}
+
+impl<'w, Q: WorldQuery, F: QueryFilter> QueryIter<'w, Q, F> {
+    /// # Safety
+    ///
+    /// The caller must guarantee nothing else borrows what `Q` accesses for `'w`.
+    pub(crate) unsafe fn new(world: &'w World, ticks: Ticks) -> Self {
+        let mut access = Access::new();
+        Q::update_access(&mut access);
+        F::update_access(&mut access);
+
+        Self {
+            world,
+            archetypes: world.archetypes().iter(),
+            ticks,
+            current: None,
+            row: 0,
+        }
+    }
+}
+
+impl<'w, Q: WorldQuery, F: QueryFilter> Iterator for QueryIter<'w, Q, F> {
+    type Item = (Entity, Q::Item<'w>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some((archetype, fetch, filter)) = self.current.as_mut() {
+                while self.row < archetype.len() {
+                    let row = self.row;
+                    let entity = archetype.entities()[row];
+                    self.row += 1;
+
+                    // SAFETY: the row is in bounds and the access was checked on creation
+                    unsafe {
+                        if Q::matches_entity(fetch, entity, row)
+                            && F::filter_row(filter, entity, row)
+                        {
+                            return Some((entity, Q::item(fetch, entity, row)));
+                        }
+                    }
+                }
+            }
+
+            let archetype = self.archetypes.next()?;
+            let world = self.world;
+            let ticks = self.ticks;
+            self.row = 0;
+            self.current = Some(archetype)
+                .filter(|a| !a.is_empty())
+                .filter(|a| Q::matches_archetype(a) && F::matches_archetype(a))
+                // SAFETY: the archetype was just matched against the query and filter
+                .map(|a| unsafe { (a, Q::fetch(world, a, ticks), F::fetch(world, a, ticks)) });
+        }
+    }
+}
+
+// Query borrowed out of a world, used as a system parameter
+pub struct Query<'w, Q: WorldQuery, F: QueryFilter = ()> {
+    world: &'w World,
+    ticks: Ticks,
+    _marker: PhantomData<fn() -> (Q, F)>,
+}
+
+impl<'w, Q: WorldQuery, F: QueryFilter> Query<'w, Q, F> {
+    /// # Safety
+    ///
+    /// Nothing else may borrow what `Q` accesses for `'w`.
+    pub(crate) unsafe fn new(world: &'w World, ticks: Ticks) -> Self {
+        Self {
+            world,
+            ticks,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn iter(&self) -> QueryIter<'_, Q, F>
+    where
+        Q: ReadOnlyWorldQuery,
+    {
+        // SAFETY: only reads, the query was handed out with the access reserved
+        unsafe { QueryIter::new(self.world, self.ticks) }
+    }
+
+    pub fn iter_mut(&mut self) -> QueryIter<'_, Q, F> {
+        // SAFETY: borrowed exclusively, the query was handed out with the access reserved
+        unsafe { QueryIter::new(self.world, self.ticks) }
+    }
+
+    pub fn get(&self, entity: Entity) -> WorldResult<Q::Item<'_>>
+    where
+        Q: ReadOnlyWorldQuery,
+    {
+        // SAFETY: only reads, the query was handed out with the access reserved
+        unsafe { self.get_unchecked(entity) }
+    }
+
+    pub fn get_mut(&mut self, entity: Entity) -> WorldResult<Q::Item<'_>> {
+        // SAFETY: borrowed exclusively, the query was handed out with the access reserved
+        unsafe { self.get_unchecked(entity) }
+    }
+
+    /// # Safety
+    ///
+    /// The item may not outlive a conflicting borrow of the same entity.
+    unsafe fn get_unchecked(&self, entity: Entity) -> WorldResult<Q::Item<'_>> {
+        let location = self.world.location(entity)?;
+        let archetype = self.world.archetypes().get(location.archetype).unwrap();
+
+        if !(Q::matches_archetype(archetype) && F::matches_archetype(archetype)) {
+            return Err(WorldError::QueryMismatch(entity));
+        }
+
+        let mut fetch = Q::fetch(self.world, archetype, self.ticks);
+        let mut filter = F::fetch(self.world, archetype, self.ticks);
+        if !(Q::matches_entity(&fetch, entity, location.row)
+            && F::filter_row(&mut filter, entity, location.row))
+        {
+            return Err(WorldError::QueryMismatch(entity));
+        }
+
+        Ok(Q::item(&mut fetch, entity, location.row))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::world::world::World;
+
+    use super::*;
+
+    #[derive(Component, Debug, PartialEq)]
+    struct Position(i32);
+
+    #[derive(Debug, PartialEq)]
+    struct Velocity(i32);
+
+    struct Frozen;
+
+    impl Component for Velocity {
+        fn component_name() -> Name {
+            Name::new("Velocity")
+        }
+    }
+
+    impl Component for Frozen {
+        fn component_name() -> Name {
+            Name::new("Frozen")
+        }
+    }
+
+    #[test]
+    fn test_query_mut() {
+        let mut world = World::new();
+
+        let a = world.spawn();
+        world.add_component(a, Position(0)).unwrap();
+        world.add_component(a, Velocity(2)).unwrap();
+
+        let b = world.spawn();
+        world.add_component(b, Position(10)).unwrap();
+        world.add_component(b, Velocity(-1)).unwrap();
+        world.add_component(b, Frozen).unwrap();
+
+        let c = world.spawn();
+        world.add_component(c, Position(5)).unwrap();
+
+        for (_, (mut position, velocity)) in
+            world.query_filtered::<(&mut Position, &Velocity), Without<Frozen>>()
+        {
+            position.0 += velocity.0;
+        }
+
+        let mut positions: Vec<_> = world
+            .query_ref::<&Position>()
+            .map(|(e, p)| (e, p.0))
+            .collect();
+        positions.sort();
+
+        assert_eq!(positions, vec![(a, 2), (b, 10), (c, 5)]);
+    }
+
+    #[test]
+    fn test_query_optional() {
+        let mut world = World::new();
+
+        let a = world.spawn();
+        world.add_component(a, Position(1)).unwrap();
+        world.add_component(a, Velocity(1)).unwrap();
+
+        let b = world.spawn();
+        world.add_component(b, Position(2)).unwrap();
+
+        let mut items: Vec<_> = world
+            .query_filtered::<(&Position, Option<&Velocity>), With<Position>>()
+            .map(|(e, (p, v))| (e, p.0, v.map(|v| v.0)))
+            .collect();
+        items.sort();
+
+        assert_eq!(items, vec![(a, 1, Some(1)), (b, 2, None)]);
+    }
+
+    #[test]
+    fn test_query_type_mismatch() {
+        // Never stored, so the name is not claimed by it
+        struct Impostor;
+
+        impl Component for Impostor {
+            fn component_name() -> Name {
+                Name::new("Velocity")
+            }
+        }
+
+        let mut world = World::new();
+        let a = world.spawn();
+        world.add_component(a, Position(1)).unwrap();
+        world.add_component(a, Velocity(1)).unwrap();
+
+        assert_eq!(world.query_ref::<&Impostor>().count(), 0);
+        let items: Vec<_> = world
+            .query_ref::<(&Position, Option<&Impostor>)>()
+            .map(|(e, (p, i))| (e, p.0, i.is_some()))
+            .collect();
+        assert_eq!(items, vec![(a, 1, false)]);
+        assert_eq!(
+            world
+                .query_ref_filtered::<&Position, Without<Impostor>>()
+                .count(),
+            1
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_query_aliasing() {
+        let mut world = World::new();
+        let _ = world.query::<(&mut Position, &Position)>();
+    }
+}
@@ -0,0 +1,518 @@
+use std::{any::Any, error::Error, fmt};
+
+use glam::Vec2;
+
+use crate::utils::Name;
+
+use super::{entity::Entity, error::WorldError, registry::ComponentRegistry, world::World};
+
+pub use khzeb_derive::Reflect;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ReflectError {
+    // The type has no field by that name
+    MissingField(&'static str, String),
+    // The text is not a valid value of the type
+    Parse(&'static str, String),
+    // The type is made of fields and has to be set field by field
+    NotParsable(&'static str),
+    // The component or resource is not registered for reflection
+    Unregistered(Name),
+    World(WorldError),
+}
+
+impl fmt::Display for ReflectError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReflectError::MissingField(ty, field) => write!(f, "{ty} has no field {field}"),
+            ReflectError::Parse(ty, text) => write!(f, "{text:?} is not a valid {ty}"),
+            ReflectError::NotParsable(ty) => write!(f, "{ty} can only be set by its fields"),
+            ReflectError::Unregistered(name) => {
+                write!(f, "{} is not registered for reflection", &**name)
+            }
+            ReflectError::World(err) => err.fmt(f),
+        }
+    }
+}
+
+impl Error for ReflectError {}
+
+impl From<WorldError> for ReflectError {
+    fn from(value: WorldError) -> Self {
+        ReflectError::World(value)
+    }
+}
+
+// Runtime view into the fields of a value, derivable with `#[derive(Reflect)]`.
+// Values without fields are set from text instead.
+pub trait Reflect: Any + Send + Sync {
+    fn reflect_type_name(&self) -> &'static str;
+
+    // In declaration order, tuple fields are named by their position
+    fn field_names(&self) -> Vec<String> {
+        vec![]
+    }
+
+    fn field(&self, _name: &str) -> Option<&dyn Reflect> {
+        None
+    }
+
+    fn field_mut(&mut self, _name: &str) -> Option<&mut dyn Reflect> {
+        None
+    }
+
+    fn set_from_str(&mut self, _text: &str) -> Result<(), ReflectError> {
+        Err(ReflectError::NotParsable(self.reflect_type_name()))
+    }
+
+    // Human-readable form for inspectors and logs
+    fn to_text(&self) -> String {
+        let fields: Vec<_> = self
+            .field_names()
+            .into_iter()
+            .filter_map(|name| {
+                let value = self.field(&name)?.to_text();
+                Some(format!("{name}: {value}"))
+            })
+            .collect();
+
+        format!(
+            "{} {{ {} }}",
+            short_type_name(self.reflect_type_name()),
+            fields.join(", ")
+        )
+    }
+
+    fn as_any(&self) -> &dyn Any;
+
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+// The name of a field and of its type
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FieldInfo {
+    pub name: String,
+    pub type_name: &'static str,
+}
+
+impl dyn Reflect {
+    pub fn fields(&self) -> Vec<FieldInfo> {
+        self.field_names()
+            .into_iter()
+            .filter_map(|name| {
+                let type_name = self.field(&name)?.reflect_type_name();
+                Some(FieldInfo { name, type_name })
+            })
+            .collect()
+    }
+
+    pub fn downcast_ref<T: Reflect>(&self) -> Option<&T> {
+        self.as_any().downcast_ref()
+    }
+
+    pub fn downcast_mut<T: Reflect>(&mut self) -> Option<&mut T> {
+        self.as_any_mut().downcast_mut()
+    }
+
+    // Follows a path of dot-separated field names, the empty path is the value itself
+    pub fn path(&self, path: &str) -> Result<&dyn Reflect, ReflectError> {
+        path.split('.')
+            .filter(|segment| !segment.is_empty())
+            .try_fold(self, |value, segment| {
+                value.field(segment).ok_or_else(|| {
+                    ReflectError::MissingField(value.reflect_type_name(), segment.to_string())
+                })
+            })
+    }
+
+    pub fn path_mut(&mut self, path: &str) -> Result<&mut dyn Reflect, ReflectError> {
+        path.split('.')
+            .filter(|segment| !segment.is_empty())
+            .try_fold(self, |value, segment| {
+                let type_name = value.reflect_type_name();
+                value
+                    .field_mut(segment)
+                    .ok_or_else(|| ReflectError::MissingField(type_name, segment.to_string()))
+            })
+    }
+
+    pub fn set_path(&mut self, path: &str, text: &str) -> Result<(), ReflectError> {
+        self.path_mut(path)?.set_from_str(text)
+    }
+}
+
+// `khzeb::world::transform::Transform` becomes `Transform`
+pub fn short_type_name(type_name: &'static str) -> &'static str {
+    let end = type_name.find('<').unwrap_or(type_name.len());
+    let start = type_name[..end].rfind("::").map_or(0, |i| i + 2);
+    &type_name[start..]
+}
+
+// Values without fields, written and read back through their text form
+macro_rules! impl_reflect_value {
+    ($($ty:ty),*) => {
+        $(
+            impl Reflect for $ty {
+                fn reflect_type_name(&self) -> &'static str {
+                    std::any::type_name::<Self>()
+                }
+
+                fn set_from_str(&mut self, text: &str) -> Result<(), ReflectError> {
+                    *self = text
+                        .parse()
+                        .map_err(|_| ReflectError::Parse(self.reflect_type_name(), text.to_string()))?;
+                    Ok(())
+                }
+
+                fn to_text(&self) -> String {
+                    format!("{self:?}")
+                }
+
+                fn as_any(&self) -> &dyn Any {
+                    self
+                }
+
+                fn as_any_mut(&mut self) -> &mut dyn Any {
+                    self
+                }
+            }
+        )*
+    };
+}
+
+impl_reflect_value!(
+    bool, char, String, i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize, f32, f64
+);
+
+// Written in the `42v3` shorthand of index 42 at generation 3
+impl Reflect for Entity {
+    fn reflect_type_name(&self) -> &'static str {
+        std::any::type_name::<Self>()
+    }
+
+    fn set_from_str(&mut self, text: &str) -> Result<(), ReflectError> {
+        *self = text
+            .parse()
+            .map_err(|_| ReflectError::Parse(self.reflect_type_name(), text.to_string()))?;
+        Ok(())
+    }
+
+    fn to_text(&self) -> String {
+        format!("{}v{}", self.index(), self.generation())
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+impl Reflect for Name {
+    fn reflect_type_name(&self) -> &'static str {
+        std::any::type_name::<Self>()
+    }
+
+    fn set_from_str(&mut self, text: &str) -> Result<(), ReflectError> {
+        *self = Name::new(text);
+        Ok(())
+    }
+
+    fn to_text(&self) -> String {
+        format!("{:?}", &**self)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+// Elements are named by their index
+impl<T: Reflect> Reflect for Vec<T> {
+    fn reflect_type_name(&self) -> &'static str {
+        std::any::type_name::<Self>()
+    }
+
+    fn field_names(&self) -> Vec<String> {
+        (0..self.len()).map(|i| i.to_string()).collect()
+    }
+
+    fn field(&self, name: &str) -> Option<&dyn Reflect> {
+        let value = self.get(name.parse::<usize>().ok()?)?;
+        Some(value)
+    }
+
+    fn field_mut(&mut self, name: &str) -> Option<&mut dyn Reflect> {
+        let value = self.get_mut(name.parse::<usize>().ok()?)?;
+        Some(value)
+    }
+
+    fn to_text(&self) -> String {
+        let items: Vec<_> = self.iter().map(|item| item.to_text()).collect();
+        format!("[{}]", items.join(", "))
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+impl Reflect for Vec2 {
+    fn reflect_type_name(&self) -> &'static str {
+        std::any::type_name::<Self>()
+    }
+
+    fn field_names(&self) -> Vec<String> {
+        vec!["x".to_string(), "y".to_string()]
+    }
+
+    fn field(&self, name: &str) -> Option<&dyn Reflect> {
+        match name {
+            "x" => Some(&self.x),
+            "y" => Some(&self.y),
+            _ => None,
+        }
+    }
+
+    fn field_mut(&mut self, name: &str) -> Option<&mut dyn Reflect> {
+        match name {
+            "x" => Some(&mut self.x),
+            "y" => Some(&mut self.y),
+            _ => None,
+        }
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+// Splits `Position.x` into the component name and the path inside it
+fn split_path(path: &str) -> (Name, &str) {
+    match path.split_once('.') {
+        Some((root, rest)) => (Name::new(root), rest),
+        None => (Name::new(path), ""),
+    }
+}
+
+impl World {
+    // Looks up `Component.field.subfield` on the entity
+    pub fn reflect_path(
+        &self,
+        entity: Entity,
+        path: &str,
+        registry: &ComponentRegistry,
+    ) -> Result<&dyn Reflect, ReflectError> {
+        let (name, rest) = split_path(path);
+        let registration = registry
+            .find(&name)
+            .ok_or_else(|| ReflectError::Unregistered(name.clone()))?;
+
+        registration.reflect(self, entity)?.path(rest)
+    }
+
+    // Parses the text into the value at `Component.field.subfield`, marking the component changed
+    pub fn set_path(
+        &mut self,
+        entity: Entity,
+        path: &str,
+        text: &str,
+        registry: &ComponentRegistry,
+    ) -> Result<(), ReflectError> {
+        let (name, rest) = split_path(path);
+        let registration = registry
+            .find(&name)
+            .ok_or_else(|| ReflectError::Unregistered(name.clone()))?;
+
+        registration.reflect_mut(self, entity)?.set_path(rest, text)
+    }
+
+    // Looks up `Resource.field.subfield`, resources go by their type name without the module path
+    pub fn reflect_resource_path(
+        &self,
+        path: &str,
+        registry: &ComponentRegistry,
+    ) -> Result<&dyn Reflect, ReflectError> {
+        let (name, rest) = split_path(path);
+        let registration = registry
+            .get_resource(&name)
+            .ok_or_else(|| ReflectError::Unregistered(name.clone()))?;
+
+        registration.reflect(self)?.path(rest)
+    }
+
+    pub fn set_resource_path(
+        &mut self,
+        path: &str,
+        text: &str,
+        registry: &ComponentRegistry,
+    ) -> Result<(), ReflectError> {
+        let (name, rest) = split_path(path);
+        let registration = registry
+            .get_resource(&name)
+            .ok_or_else(|| ReflectError::Unregistered(name.clone()))?;
+
+        registration.reflect_mut(self)?.set_path(rest, text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::world::{change::Changed, component::Component, transform::Transform};
+
+    use super::*;
+
+    #[derive(Reflect, Debug, PartialEq)]
+    struct Position {
+        x: f32,
+        y: f32,
+        #[reflect(skip)]
+        cache: Option<u8>,
+    }
+
+    impl Component for Position {
+        fn component_name() -> Name {
+            Name::new("Position")
+        }
+    }
+
+    #[derive(Reflect)]
+    struct Time {
+        delta: f32,
+        frames: u64,
+    }
+
+    #[derive(Reflect)]
+    struct Tag(u32, String);
+
+    #[test]
+    fn test_fields() {
+        let tag: &dyn Reflect = &Tag(3, "three".to_string());
+
+        assert_eq!(
+            tag.fields(),
+            vec![
+                FieldInfo {
+                    name: "0".to_string(),
+                    type_name: "u32"
+                },
+                FieldInfo {
+                    name: "1".to_string(),
+                    type_name: std::any::type_name::<String>()
+                },
+            ]
+        );
+        assert_eq!(tag.to_text(), "Tag { 0: 3, 1: \"three\" }");
+
+        let mut transform = Transform::default();
+        let value: &mut dyn Reflect = &mut transform;
+        value.set_path("translation.y", "2.5").unwrap();
+        assert_eq!(transform.translation.y, 2.5);
+
+        let value: &mut dyn Reflect = &mut transform;
+        assert_eq!(
+            value.set_path("translation.z", "1"),
+            Err(ReflectError::MissingField(
+                std::any::type_name::<Vec2>(),
+                "z".to_string()
+            ))
+        );
+        assert!(matches!(
+            value.set_path("rotation", "pi"),
+            Err(ReflectError::Parse(_, _))
+        ));
+        assert!(matches!(
+            value.set_path("translation", "1"),
+            Err(ReflectError::NotParsable(_))
+        ));
+    }
+
+    #[test]
+    fn test_console_set() {
+        let mut registry = ComponentRegistry::new();
+        registry.register_reflect::<Position>().unwrap();
+        registry.register_resource_reflect::<Time>();
+
+        let mut world = World::new();
+        let entity = world.spawn();
+        world
+            .add_component(
+                entity,
+                Position {
+                    x: 0.,
+                    y: 1.,
+                    cache: Some(1),
+                },
+            )
+            .unwrap();
+        world.insert_resource(Time {
+            delta: 0.,
+            frames: 0,
+        });
+        world.clear_trackers();
+
+        let command = format!(
+            "set {}v{} Position.x 10",
+            entity.index(),
+            entity.generation()
+        );
+        let [verb, target, path, value] = command.split_whitespace().collect::<Vec<_>>()[..] else {
+            panic!("Malformed command");
+        };
+        assert_eq!(verb, "set");
+
+        let target: Entity = target.parse().unwrap();
+        world.set_path(target, path, value, &registry).unwrap();
+
+        assert_eq!(
+            world.get::<Position>(entity).unwrap(),
+            &Position {
+                x: 10.,
+                y: 1.,
+                cache: Some(1)
+            }
+        );
+        assert_eq!(
+            world
+                .reflect_path(entity, "Position.y", &registry)
+                .unwrap()
+                .to_text(),
+            "1.0"
+        );
+        assert_eq!(
+            world.query_filtered::<Entity, Changed<Position>>().count(),
+            1
+        );
+
+        // Engine components are found without their module path
+        world.add_component(entity, Transform::default()).unwrap();
+        world
+            .set_path(entity, "Transform.translation.x", "2", &registry)
+            .unwrap();
+        assert_eq!(world.get::<Transform>(entity).unwrap().translation.x, 2.);
+
+        world
+            .set_resource_path("Time.frames", "7", &registry)
+            .unwrap();
+        assert_eq!(world.resource::<Time>().unwrap().frames, 7);
+
+        assert_eq!(
+            world.set_path(entity, "Velocity.x", "1", &registry),
+            Err(ReflectError::Unregistered(Name::new("Velocity")))
+        );
+    }
+}
@@ -0,0 +1,266 @@
+use std::{any::type_name, collections::HashMap};
+
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+
+use crate::utils::Name;
+
+use super::{
+    archetype::{ArchetypeRow, Column},
+    component::{register_component_type, Component, NameCollision},
+    entity::Entity,
+    error::WorldError,
+    hierarchy::{Children, Parent},
+    reflect::{short_type_name, Reflect, ReflectError},
+    snapshot::SnapshotError,
+    transform::{GlobalTransform, Transform},
+    world::World,
+};
+
+type SerializeFn = fn(&Column, ArchetypeRow) -> Result<Value, SnapshotError>;
+type InsertFn = fn(&mut World, Entity, Value) -> Result<(), SnapshotError>;
+type ReflectFn = for<'w> fn(&'w World, Entity) -> Result<&'w dyn Reflect, WorldError>;
+type ReflectMutFn = for<'w> fn(&'w mut World, Entity) -> Result<&'w mut dyn Reflect, WorldError>;
+
+#[derive(Clone, Copy)]
+struct SerdeFns {
+    serialize: SerializeFn,
+    insert: InsertFn,
+}
+
+#[derive(Clone, Copy)]
+struct ReflectFns {
+    get: ReflectFn,
+    get_mut: ReflectMutFn,
+}
+
+// Type-erased operations on a component known by its name
+#[derive(Clone)]
+pub struct ComponentRegistration {
+    name: Name,
+    type_name: &'static str,
+    serde: Option<SerdeFns>,
+    reflect: Option<ReflectFns>,
+}
+
+impl ComponentRegistration {
+    fn new<T: Component>() -> Self {
+        Self {
+            name: T::component_name(),
+            type_name: type_name::<T>(),
+            serde: None,
+            reflect: None,
+        }
+    }
+
+    pub fn name(&self) -> &Name {
+        &self.name
+    }
+
+    pub fn type_name(&self) -> &'static str {
+        self.type_name
+    }
+
+    pub fn serialize(&self, column: &Column, row: ArchetypeRow) -> Result<Value, SnapshotError> {
+        let serde = self
+            .serde
+            .ok_or_else(|| SnapshotError::Unregistered(self.name.clone()))?;
+        (serde.serialize)(column, row)
+    }
+
+    // Deserializes the value and adds it to the entity, overwriting the previous one
+    pub fn insert(
+        &self,
+        world: &mut World,
+        entity: Entity,
+        value: Value,
+    ) -> Result<(), SnapshotError> {
+        let serde = self
+            .serde
+            .ok_or_else(|| SnapshotError::Unregistered(self.name.clone()))?;
+        (serde.insert)(world, entity, value)
+    }
+
+    pub fn reflect<'w>(
+        &self,
+        world: &'w World,
+        entity: Entity,
+    ) -> Result<&'w dyn Reflect, ReflectError> {
+        let reflect = self
+            .reflect
+            .ok_or_else(|| ReflectError::Unregistered(self.name.clone()))?;
+        Ok((reflect.get)(world, entity)?)
+    }
+
+    // Marks the component changed
+    pub fn reflect_mut<'w>(
+        &self,
+        world: &'w mut World,
+        entity: Entity,
+    ) -> Result<&'w mut dyn Reflect, ReflectError> {
+        let reflect = self
+            .reflect
+            .ok_or_else(|| ReflectError::Unregistered(self.name.clone()))?;
+        Ok((reflect.get_mut)(world, entity)?)
+    }
+}
+
+// Type-erased access to a resource known by its name
+#[derive(Clone)]
+pub struct ResourceRegistration {
+    name: Name,
+    get: for<'w> fn(&'w World) -> Result<&'w dyn Reflect, WorldError>,
+    get_mut: for<'w> fn(&'w mut World) -> Result<&'w mut dyn Reflect, WorldError>,
+}
+
+impl ResourceRegistration {
+    pub fn name(&self) -> &Name {
+        &self.name
+    }
+
+    pub fn reflect<'w>(&self, world: &'w World) -> Result<&'w dyn Reflect, ReflectError> {
+        Ok((self.get)(world)?)
+    }
+
+    // Marks the resource changed
+    pub fn reflect_mut<'w>(
+        &self,
+        world: &'w mut World,
+    ) -> Result<&'w mut dyn Reflect, ReflectError> {
+        Ok((self.get_mut)(world)?)
+    }
+}
+
+// The components that can be written out, read back and inspected, by name.
+// Labels (`Name` components) are always known and not registered.
+#[derive(Clone)]
+pub struct ComponentRegistry {
+    registrations: HashMap<Name, ComponentRegistration>,
+    // By the type name without the module path
+    resources: HashMap<Name, ResourceRegistration>,
+}
+
+impl Default for ComponentRegistry {
+    fn default() -> Self {
+        let mut registry = Self {
+            registrations: HashMap::new(),
+            resources: HashMap::new(),
+        };
+
+        registry
+            .register_engine()
+            .expect("Engine components claim their names first");
+
+        registry
+    }
+}
+
+impl ComponentRegistry {
+    // Comes with the components of the engine itself registered
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn register_engine(&mut self) -> Result<(), NameCollision> {
+        self.register::<()>()?
+            .register::<Parent>()?
+            .register::<Children>()?
+            .register::<Transform>()?
+            .register::<GlobalTransform>()?
+            .register_reflect::<Parent>()?
+            .register_reflect::<Children>()?
+            .register_reflect::<Transform>()?;
+        Ok(())
+    }
+
+    fn entry<T: Component>(&mut self) -> Result<&mut ComponentRegistration, NameCollision> {
+        let name = T::component_name();
+        register_component_type::<T>(&name)?;
+
+        Ok(self
+            .registrations
+            .entry(name)
+            .or_insert_with(ComponentRegistration::new::<T>))
+    }
+
+    // Makes the component part of snapshots and deltas
+    pub fn register<T: Component + Serialize + DeserializeOwned>(
+        &mut self,
+    ) -> Result<&mut Self, NameCollision> {
+        self.entry::<T>()?.serde = Some(SerdeFns {
+            serialize: |column, row| {
+                let value = column
+                    .as_slice::<T>()
+                    .and_then(|slice| slice.get(row))
+                    .ok_or_else(|| SnapshotError::TypeMismatch(T::component_name()))?;
+                Ok(serde_json::to_value(value)?)
+            },
+            insert: |world, entity, value| {
+                let component: T = serde_json::from_value(value)?;
+                Ok(world.add_component(entity, component)?)
+            },
+        });
+        Ok(self)
+    }
+
+    // Makes the fields of the component reachable by path
+    pub fn register_reflect<T: Component + Reflect>(&mut self) -> Result<&mut Self, NameCollision> {
+        self.entry::<T>()?.reflect = Some(ReflectFns {
+            get: |world, entity| Ok(world.get::<T>(entity)? as &dyn Reflect),
+            get_mut: |world, entity| {
+                Ok(world.get_mut::<T>(entity)?.into_inner() as &mut dyn Reflect)
+            },
+        });
+        Ok(self)
+    }
+
+    pub fn register_resource_reflect<R: Reflect>(&mut self) -> &mut Self {
+        let name = Name::new(short_type_name(type_name::<R>()));
+        self.resources.insert(
+            name.clone(),
+            ResourceRegistration {
+                name,
+                get: |world| Ok(world.resource::<R>()? as &dyn Reflect),
+                get_mut: |world| Ok(world.resource_mut::<R>()?.into_inner() as &mut dyn Reflect),
+            },
+        );
+        self
+    }
+
+    pub fn get(&self, name: &Name) -> Option<&ComponentRegistration> {
+        self.registrations.get(name)
+    }
+
+    // Falls back to the name without the module path when it's unambiguous, so `Transform` does
+    pub fn find(&self, name: &Name) -> Option<&ComponentRegistration> {
+        if let Some(registration) = self.registrations.get(name) {
+            return Some(registration);
+        }
+
+        let mut matches = self
+            .registrations
+            .values()
+            .filter(|registration| registration.name.rsplit("::").next() == Some(&**name));
+
+        match (matches.next(), matches.next()) {
+            (Some(registration), None) => Some(registration),
+            _ => None,
+        }
+    }
+
+    pub fn contains(&self, name: &Name) -> bool {
+        self.registrations.contains_key(name)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &ComponentRegistration> {
+        self.registrations.values()
+    }
+
+    pub fn get_resource(&self, name: &Name) -> Option<&ResourceRegistration> {
+        self.resources.get(name)
+    }
+
+    pub fn iter_resources(&self) -> impl Iterator<Item = &ResourceRegistration> {
+        self.resources.values()
+    }
+}
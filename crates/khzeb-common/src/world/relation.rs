@@ -0,0 +1,333 @@
+use std::{collections::HashMap, marker::PhantomData};
+
+use super::{commands::Commands, entity::Entity, error::WorldResult, world::World};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Cleanup {
+    // Only the pairs with the despawned entity go away
+    Unlink,
+    // The entities on the other end of the pairs are despawned as well
+    Despawn,
+}
+
+// A kind of directed link between two entities, like `Targets` or `InInventoryOf`.
+// An entity can be the source and the target of any number of pairs.
+pub trait Relation: Send + Sync + 'static {
+    // What happens to the targets when a source is despawned
+    const ON_SOURCE_DESPAWN: Cleanup = Cleanup::Unlink;
+    // What happens to the sources when a target is despawned
+    const ON_TARGET_DESPAWN: Cleanup = Cleanup::Unlink;
+}
+
+// Every pair of relation `R`, indexed in both directions.
+// Stored as a resource, so systems read it with `Res<RelationPairs<R>>`.
+pub struct RelationPairs<R: Relation> {
+    targets: HashMap<Entity, Vec<Entity>>,
+    sources: HashMap<Entity, Vec<Entity>>,
+    len: usize,
+    marker: PhantomData<fn() -> R>,
+}
+
+impl<R: Relation> Default for RelationPairs<R> {
+    fn default() -> Self {
+        Self {
+            targets: Default::default(),
+            sources: Default::default(),
+            len: 0,
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<R: Relation> RelationPairs<R> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // The entities the source is related to, in the order they were related
+    pub fn targets(&self, source: Entity) -> &[Entity] {
+        self.targets
+            .get(&source)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    // The entities related to the target, in the order they were related
+    pub fn sources(&self, target: Entity) -> &[Entity] {
+        self.sources
+            .get(&target)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    pub fn contains(&self, source: Entity, target: Entity) -> bool {
+        self.targets(source).contains(&target)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (Entity, Entity)> + '_ {
+        self.targets
+            .iter()
+            .flat_map(|(&source, targets)| targets.iter().map(move |&target| (source, target)))
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    // The pair must not exist yet
+    fn insert(&mut self, source: Entity, target: Entity) {
+        self.targets.entry(source).or_default().push(target);
+        self.sources.entry(target).or_default().push(source);
+        self.len += 1;
+    }
+
+    // Returns whether the pair existed
+    fn remove(&mut self, source: Entity, target: Entity) -> bool {
+        if !unlink(&mut self.targets, source, target) {
+            return false;
+        }
+
+        unlink(&mut self.sources, target, source);
+        self.len -= 1;
+        true
+    }
+
+    fn involves(&self, entity: Entity) -> bool {
+        self.targets.contains_key(&entity) || self.sources.contains_key(&entity)
+    }
+
+    // Drops every pair with the entity on either end, returning its targets and its sources
+    fn forget(&mut self, entity: Entity) -> (Vec<Entity>, Vec<Entity>) {
+        let targets = self.targets.remove(&entity).unwrap_or_default();
+        let sources = self.sources.remove(&entity).unwrap_or_default();
+
+        for &target in &targets {
+            unlink(&mut self.sources, target, entity);
+        }
+        for &source in &sources {
+            unlink(&mut self.targets, source, entity);
+        }
+
+        // A pair of the entity with itself is in both lists
+        let looped = targets.contains(&entity) as usize;
+        self.len -= targets.len() + sources.len() - looped;
+
+        (targets, sources)
+    }
+}
+
+// Removes `to` from the list of `from`, dropping the list once empty
+fn unlink(lists: &mut HashMap<Entity, Vec<Entity>>, from: Entity, to: Entity) -> bool {
+    let Some(list) = lists.get_mut(&from) else {
+        return false;
+    };
+    let Some(idx) = list.iter().position(|&e| e == to) else {
+        return false;
+    };
+
+    list.remove(idx);
+    if list.is_empty() {
+        lists.remove(&from);
+    }
+    true
+}
+
+// Cleanups of every relation used in a world, run on every despawn.
+// They collect the entities that go down with the despawned one.
+#[derive(Default)]
+pub(crate) struct RelationCleanups(Vec<fn(&mut World, Entity, &mut Vec<Entity>)>);
+
+impl World {
+    // Relates the entities unless they already are
+    pub fn relate<R: Relation>(&mut self, source: Entity, target: Entity) -> WorldResult<()> {
+        self.location(source)?;
+        self.location(target)?;
+
+        if !self.contains_resource::<RelationPairs<R>>() {
+            self.add_relation::<R>();
+        }
+
+        let mut pairs = self.resource_mut::<RelationPairs<R>>()?;
+        if !pairs.contains(source, target) {
+            pairs.insert(source, target);
+        }
+        Ok(())
+    }
+
+    // Returns whether the entities were related
+    pub fn unrelate<R: Relation>(&mut self, source: Entity, target: Entity) -> bool {
+        match self.resource_mut::<RelationPairs<R>>() {
+            Ok(mut pairs) if pairs.contains(source, target) => pairs.remove(source, target),
+            _ => false,
+        }
+    }
+
+    pub fn relation_pairs<R: Relation>(&self) -> Option<&RelationPairs<R>> {
+        self.resource::<RelationPairs<R>>().ok()
+    }
+
+    pub fn targets<R: Relation>(&self, source: Entity) -> &[Entity] {
+        self.relation_pairs::<R>()
+            .map(|pairs| pairs.targets(source))
+            .unwrap_or_default()
+    }
+
+    pub fn sources<R: Relation>(&self, target: Entity) -> &[Entity] {
+        self.relation_pairs::<R>()
+            .map(|pairs| pairs.sources(target))
+            .unwrap_or_default()
+    }
+
+    fn add_relation<R: Relation>(&mut self) {
+        self.insert_resource(RelationPairs::<R>::new());
+
+        if !self.contains_resource::<RelationCleanups>() {
+            self.insert_resource(RelationCleanups::default());
+        }
+        self.resource_mut::<RelationCleanups>()
+            .unwrap()
+            .0
+            .push(|world, entity, cascade| {
+                let Ok(mut pairs) = world.resource_mut::<RelationPairs<R>>() else {
+                    return;
+                };
+                if !pairs.involves(entity) {
+                    return;
+                }
+
+                let (targets, sources) = pairs.forget(entity);
+                if R::ON_SOURCE_DESPAWN == Cleanup::Despawn {
+                    cascade.extend(targets);
+                }
+                if R::ON_TARGET_DESPAWN == Cleanup::Despawn {
+                    cascade.extend(sources);
+                }
+            });
+    }
+
+    // Drops the pairs of a despawned entity, returning the entities to despawn along with it
+    pub(crate) fn cleanup_relations(&mut self, entity: Entity) -> Vec<Entity> {
+        let mut cascade = vec![];
+
+        // Looked up one at a time, cleanups are plain functions and copied right out
+        let mut idx = 0;
+        while let Some(cleanup) = self
+            .resource::<RelationCleanups>()
+            .ok()
+            .and_then(|cleanups| cleanups.0.get(idx).copied())
+        {
+            cleanup(self, entity, &mut cascade);
+            idx += 1;
+        }
+
+        cascade
+    }
+}
+
+impl Commands<'_> {
+    pub fn relate<R: Relation>(&mut self, source: Entity, target: Entity) {
+        self.add(move |world| {
+            world.relate::<R>(source, target).ok();
+        });
+    }
+
+    pub fn unrelate<R: Relation>(&mut self, source: Entity, target: Entity) {
+        self.add(move |world| {
+            world.unrelate::<R>(source, target);
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::world::error::WorldError;
+
+    use super::*;
+
+    struct Likes;
+
+    impl Relation for Likes {}
+
+    // Items go away with their owner, but not the other way around
+    struct InInventoryOf;
+
+    impl Relation for InInventoryOf {
+        const ON_TARGET_DESPAWN: Cleanup = Cleanup::Despawn;
+    }
+
+    #[test]
+    fn test_relations() {
+        let mut world = World::new();
+        let [a, b, c] = [world.spawn(), world.spawn(), world.spawn()];
+
+        world.relate::<Likes>(a, b).unwrap();
+        world.relate::<Likes>(a, c).unwrap();
+        world.relate::<Likes>(c, b).unwrap();
+        world.relate::<Likes>(a, b).unwrap();
+        world.relate::<Likes>(b, b).unwrap();
+
+        assert_eq!(world.targets::<Likes>(a), &[b, c]);
+        assert_eq!(world.sources::<Likes>(b), &[a, c, b]);
+        assert_eq!(world.relation_pairs::<Likes>().unwrap().len(), 4);
+        // Relations of different kinds are separate
+        assert!(world.targets::<InInventoryOf>(a).is_empty());
+
+        assert!(world.unrelate::<Likes>(a, c));
+        assert!(!world.unrelate::<Likes>(a, c));
+        assert_eq!(world.targets::<Likes>(a), &[b]);
+
+        world.despawn(b).unwrap();
+        assert!(world.targets::<Likes>(a).is_empty());
+        assert!(world.targets::<Likes>(c).is_empty());
+        assert!(world.relation_pairs::<Likes>().unwrap().is_empty());
+        assert!(world.is_alive(a) && world.is_alive(c));
+
+        assert_eq!(world.relate::<Likes>(a, b), Err(WorldError::StaleEntity(b)));
+    }
+
+    #[test]
+    fn test_cleanup() {
+        let mut world = World::new();
+        let owner = world.spawn();
+        let bag = world.spawn();
+        let coin = world.spawn();
+        let friend = world.spawn();
+
+        world.relate::<InInventoryOf>(bag, owner).unwrap();
+        world.relate::<InInventoryOf>(coin, bag).unwrap();
+        world.relate::<Likes>(friend, coin).unwrap();
+
+        // Despawning an item leaves its owner alone
+        let sword = world.spawn();
+        world.relate::<InInventoryOf>(sword, owner).unwrap();
+        world.despawn(sword).unwrap();
+        assert!(world.is_alive(owner));
+
+        world.despawn(owner).unwrap();
+        assert!(!world.is_alive(bag));
+        assert!(!world.is_alive(coin));
+        assert!(world.is_alive(friend));
+        assert!(world.targets::<Likes>(friend).is_empty());
+    }
+
+    #[test]
+    fn test_long_cascade() {
+        let mut world = World::new();
+        let first = world.spawn();
+
+        let mut last = first;
+        for _ in 0..100_000 {
+            let next = world.spawn();
+            world.relate::<InInventoryOf>(next, last).unwrap();
+            last = next;
+        }
+
+        world.despawn(first).unwrap();
+        assert!(!world.is_alive(last));
+        assert!(world.relation_pairs::<InInventoryOf>().unwrap().is_empty());
+    }
+}
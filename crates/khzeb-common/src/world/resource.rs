@@ -0,0 +1,373 @@
+use std::{
+    any::{type_name, Any, TypeId},
+    cell::UnsafeCell,
+    collections::HashMap,
+    ops::{Deref, DerefMut},
+};
+
+use crate::utils::Name;
+
+use super::{
+    change::{ComponentTicks, Mut, Tick, Ticks},
+    error::{WorldError, WorldResult},
+    query::Access,
+    system::SystemParam,
+    world::World,
+};
+
+// The name a resource is known by in errors and system access
+pub fn resource_name<R: 'static>() -> Name {
+    Name::from_static(type_name::<R>())
+}
+
+struct ResourceData {
+    value: UnsafeCell<Box<dyn Any + Send + Sync>>,
+    ticks: UnsafeCell<ComponentTicks>,
+}
+
+// Singletons owned by the world, one per type
+#[derive(Default)]
+pub struct Resources {
+    resources: HashMap<TypeId, ResourceData>,
+}
+
+// Borrows of the cells are checked by the system access, the same way columns are
+unsafe impl Sync for Resources {}
+
+impl Resources {
+    pub(crate) fn insert<R: Send + Sync + 'static>(&mut self, resource: R, tick: Tick) {
+        match self.resources.get_mut(&TypeId::of::<R>()) {
+            Some(data) => {
+                *data.value.get_mut() = Box::new(resource);
+                data.ticks.get_mut().changed = tick;
+            }
+            None => {
+                self.resources.insert(
+                    TypeId::of::<R>(),
+                    ResourceData {
+                        value: UnsafeCell::new(Box::new(resource)),
+                        ticks: UnsafeCell::new(ComponentTicks::new(tick)),
+                    },
+                );
+            }
+        }
+    }
+
+    pub(crate) fn remove<R: 'static>(&mut self) -> Option<R> {
+        let data = self.resources.remove(&TypeId::of::<R>())?;
+        data.value.into_inner().downcast().ok().map(|r| *r)
+    }
+
+    pub fn contains<R: 'static>(&self) -> bool {
+        self.resources.contains_key(&TypeId::of::<R>())
+    }
+
+    pub fn len(&self) -> usize {
+        self.resources.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.resources.is_empty()
+    }
+
+    /// # Safety
+    ///
+    /// The resource may not be borrowed mutably while the result is alive.
+    pub(crate) unsafe fn get<R: 'static>(&self) -> Option<(&R, &ComponentTicks)> {
+        let data = self.resources.get(&TypeId::of::<R>())?;
+        let value = (*data.value.get()).downcast_ref::<R>()?;
+        Some((value, &*data.ticks.get()))
+    }
+
+    /// # Safety
+    ///
+    /// The resource may not be borrowed at all while the result is alive.
+    #[allow(clippy::mut_from_ref)]
+    pub(crate) unsafe fn get_mut<R: 'static>(&self) -> Option<(&mut R, &mut ComponentTicks)> {
+        let data = self.resources.get(&TypeId::of::<R>())?;
+        let value = (*data.value.get()).downcast_mut::<R>()?;
+        Some((value, &mut *data.ticks.get()))
+    }
+}
+
+// Shared borrow of a world resource, used as a system parameter
+pub struct Res<'w, R> {
+    value: &'w R,
+    ticks: &'w ComponentTicks,
+    last_run: Tick,
+}
+
+impl<'w, R: 'static> Res<'w, R> {
+    /// # Safety
+    ///
+    /// Nothing else may borrow the resource mutably for `'w`.
+    pub(crate) unsafe fn new(world: &'w World, ticks: Ticks) -> Self {
+        let (value, component_ticks) = world
+            .resources()
+            .get::<R>()
+            .unwrap_or_else(|| panic!("Resource {} does not exist", type_name::<R>()));
+
+        Res {
+            value,
+            ticks: component_ticks,
+            last_run: ticks.last_run,
+        }
+    }
+}
+
+impl<R> Res<'_, R> {
+    pub fn is_added(&self) -> bool {
+        self.ticks.is_added(self.last_run)
+    }
+
+    pub fn is_changed(&self) -> bool {
+        self.ticks.is_changed(self.last_run)
+    }
+}
+
+impl<R> Deref for Res<'_, R> {
+    type Target = R;
+
+    fn deref(&self) -> &Self::Target {
+        self.value
+    }
+}
+
+// Exclusive borrow of a world resource, used as a system parameter
+pub struct ResMut<'w, R> {
+    value: Mut<'w, R>,
+    last_run: Tick,
+}
+
+impl<'w, R: 'static> ResMut<'w, R> {
+    /// # Safety
+    ///
+    /// Nothing else may borrow the resource for `'w`.
+    pub(crate) unsafe fn new(world: &'w World, ticks: Ticks) -> Self {
+        let (value, component_ticks) = world
+            .resources()
+            .get_mut::<R>()
+            .unwrap_or_else(|| panic!("Resource {} does not exist", type_name::<R>()));
+
+        ResMut {
+            value: Mut::new(value, component_ticks, ticks.this_run),
+            last_run: ticks.last_run,
+        }
+    }
+}
+
+impl<R> ResMut<'_, R> {
+    pub fn is_added(&self) -> bool {
+        self.value.ticks().is_added(self.last_run)
+    }
+
+    pub fn is_changed(&self) -> bool {
+        self.value.ticks().is_changed(self.last_run)
+    }
+}
+
+impl<R> Deref for ResMut<'_, R> {
+    type Target = R;
+
+    fn deref(&self) -> &Self::Target {
+        &self.value
+    }
+}
+
+impl<R> DerefMut for ResMut<'_, R> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.value
+    }
+}
+
+impl<R: Send + Sync + 'static> SystemParam for Res<'_, R> {
+    type Item<'w> = Res<'w, R>;
+    type State = ();
+
+    fn init_state() -> Self::State {}
+
+    fn update_access(access: &mut Access) {
+        access.add_resource_read(resource_name::<R>());
+    }
+
+    unsafe fn fetch<'w>(_: &'w mut (), world: &'w World, ticks: Ticks) -> Self::Item<'w> {
+        Res::new(world, ticks)
+    }
+}
+
+impl<R: Send + Sync + 'static> SystemParam for ResMut<'_, R> {
+    type Item<'w> = ResMut<'w, R>;
+    type State = ();
+
+    fn init_state() -> Self::State {}
+
+    fn update_access(access: &mut Access) {
+        access.add_resource_write(resource_name::<R>());
+    }
+
+    unsafe fn fetch<'w>(_: &'w mut (), world: &'w World, ticks: Ticks) -> Self::Item<'w> {
+        ResMut::new(world, ticks)
+    }
+}
+
+// Resources that may be missing, for systems that work without them
+impl<R: Send + Sync + 'static> SystemParam for Option<Res<'_, R>> {
+    type Item<'w> = Option<Res<'w, R>>;
+    type State = ();
+
+    fn init_state() -> Self::State {}
+
+    fn update_access(access: &mut Access) {
+        Res::<R>::update_access(access);
+    }
+
+    unsafe fn fetch<'w>(state: &'w mut (), world: &'w World, ticks: Ticks) -> Self::Item<'w> {
+        world
+            .resources()
+            .contains::<R>()
+            .then(|| Res::<R>::fetch(state, world, ticks))
+    }
+}
+
+impl<R: Send + Sync + 'static> SystemParam for Option<ResMut<'_, R>> {
+    type Item<'w> = Option<ResMut<'w, R>>;
+    type State = ();
+
+    fn init_state() -> Self::State {}
+
+    fn update_access(access: &mut Access) {
+        ResMut::<R>::update_access(access);
+    }
+
+    unsafe fn fetch<'w>(state: &'w mut (), world: &'w World, ticks: Ticks) -> Self::Item<'w> {
+        world
+            .resources()
+            .contains::<R>()
+            .then(|| ResMut::<R>::fetch(state, world, ticks))
+    }
+}
+
+impl World {
+    pub fn insert_resource<R: Send + Sync + 'static>(&mut self, resource: R) {
+        let tick = self.change_tick();
+        self.resources_mut().insert(resource, tick);
+    }
+
+    pub fn remove_resource<R: 'static>(&mut self) -> Option<R> {
+        self.resources_mut().remove()
+    }
+
+    pub fn contains_resource<R: 'static>(&self) -> bool {
+        self.resources().contains::<R>()
+    }
+
+    pub fn resource<R: 'static>(&self) -> WorldResult<&R> {
+        // SAFETY: mutable borrows need the world borrowed exclusively
+        unsafe { self.resources().get::<R>() }
+            .map(|(value, _)| value)
+            .ok_or_else(|| WorldError::MissingResource(resource_name::<R>()))
+    }
+
+    pub fn resource_mut<R: 'static>(&mut self) -> WorldResult<Mut<'_, R>> {
+        let tick = self.change_tick();
+        // SAFETY: the world is borrowed exclusively
+        unsafe { self.resources().get_mut::<R>() }
+            .map(|(value, ticks)| Mut::new(value, ticks, tick))
+            .ok_or_else(|| WorldError::MissingResource(resource_name::<R>()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        utils::{Registry, Resource},
+        world::{
+            component::Component,
+            query::Query,
+            schedule::{Executor, Schedule, Stage},
+        },
+    };
+
+    use super::*;
+
+    #[derive(Default)]
+    struct Time {
+        elapsed: f32,
+        delta: f32,
+    }
+
+    #[derive(Component)]
+    struct Position(f32);
+
+    fn tick(mut time: ResMut<Time>) {
+        time.elapsed += time.delta;
+    }
+
+    fn movement(time: Res<Time>, mut query: Query<&mut Position>) {
+        for (_, mut position) in query.iter_mut() {
+            position.0 += time.delta;
+        }
+    }
+
+    fn lookup(registry: Res<Registry>, mut query: Query<&mut Position>) {
+        let offset = registry.get(Resource::<f32>::new("offset")).unwrap();
+        for (_, mut position) in query.iter_mut() {
+            position.0 += offset;
+        }
+    }
+
+    #[test]
+    fn test_world_resources() {
+        let mut world = World::new();
+        assert!(world.resource::<Time>().is_err());
+
+        world.insert_resource(Time {
+            elapsed: 0.,
+            delta: 0.5,
+        });
+        world.resource_mut::<Time>().unwrap().elapsed = 2.;
+        assert_eq!(world.resource::<Time>().unwrap().elapsed, 2.);
+
+        world.insert_resource(Time::default());
+        assert_eq!(world.resource::<Time>().unwrap().delta, 0.);
+
+        assert!(world.remove_resource::<Time>().is_some());
+        assert!(!world.contains_resource::<Time>());
+    }
+
+    #[test]
+    fn test_resource_params() {
+        let mut world = World::new();
+        world.insert_resource(Time {
+            elapsed: 0.,
+            delta: 0.5,
+        });
+
+        let mut registry = Registry::new();
+        registry.put("offset", 10f32);
+        world.insert_resource(registry);
+
+        let a = world.spawn();
+        world.add_component(a, Position(0.)).unwrap();
+
+        let mut schedule = Schedule::new().with_executor(Executor::SingleThreaded);
+        schedule.add_system(Stage::PreUpdate, tick);
+        schedule.add_system(Stage::Update, movement);
+        schedule.add_system(Stage::PostUpdate, lookup);
+
+        schedule.run(&mut world).unwrap();
+        schedule.run(&mut world).unwrap();
+
+        assert_eq!(world.resource::<Time>().unwrap().elapsed, 1.);
+        assert_eq!(world.get::<Position>(a).unwrap().0, 21.);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_resource_aliasing() {
+        fn aliased(_: Res<Time>, _: ResMut<Time>) {}
+
+        let mut schedule = Schedule::new();
+        schedule.add_system(Stage::Update, aliased);
+    }
+}
@@ -0,0 +1,468 @@
+use std::{error::Error, fmt};
+
+use rayon::{ThreadPool, ThreadPoolBuilder};
+
+use crate::utils::Name;
+
+use super::{
+    system::{system_name, IntoSystem, System},
+    world::World,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Stage {
+    PreUpdate,
+    Update,
+    PostUpdate,
+    Render,
+}
+
+impl Stage {
+    pub const ALL: [Stage; 4] = [
+        Stage::PreUpdate,
+        Stage::Update,
+        Stage::PostUpdate,
+        Stage::Render,
+    ];
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Executor {
+    // Runs every system in order on the calling thread, for debugging and replays
+    SingleThreaded,
+    // Runs systems with compatible access side by side, `None` picks a thread per core
+    MultiThreaded { threads: Option<usize> },
+}
+
+impl Default for Executor {
+    fn default() -> Self {
+        Executor::MultiThreaded { threads: None }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ScheduleError {
+    // The systems listed form a loop of `before`/`after` constraints, in order
+    Cycle { stage: Stage, systems: Vec<Name> },
+}
+
+impl fmt::Display for ScheduleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScheduleError::Cycle { stage, systems } => {
+                write!(f, "Ordering cycle in stage {stage:?}: ")?;
+                for name in systems {
+                    write!(f, "{} -> ", &**name)?;
+                }
+                write!(f, "{}", &*systems[0])
+            }
+        }
+    }
+}
+
+impl Error for ScheduleError {}
+
+struct SystemNode {
+    system: Box<dyn System>,
+    stage: Stage,
+    before: Vec<Name>,
+    after: Vec<Name>,
+}
+
+// Handle for configuring the ordering of a freshly added system
+pub struct SystemConfig<'s> {
+    node: &'s mut SystemNode,
+}
+
+impl SystemConfig<'_> {
+    pub fn before<Marker>(self, other: impl IntoSystem<Marker>) -> Self {
+        self.node.before.push(system_name(&other));
+        self
+    }
+
+    pub fn after<Marker>(self, other: impl IntoSystem<Marker>) -> Self {
+        self.node.after.push(system_name(&other));
+        self
+    }
+}
+
+#[derive(Default)]
+pub struct Schedule {
+    systems: Vec<SystemNode>,
+    // Indices into `systems` grouped into batches that may run at the same time,
+    // in execution order, for every stage. `None` when systems changed since.
+    batches: Option<Vec<Vec<Vec<usize>>>>,
+
+    executor: Executor,
+    thread_pool: Option<ThreadPool>,
+}
+
+impl Schedule {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_executor(self, executor: Executor) -> Self {
+        Self {
+            executor,
+            thread_pool: None,
+            ..self
+        }
+    }
+
+    pub fn set_executor(&mut self, executor: Executor) {
+        if self.executor != executor {
+            self.executor = executor;
+            self.thread_pool = None;
+        }
+    }
+
+    pub fn executor(&self) -> Executor {
+        self.executor
+    }
+
+    pub fn add_system<Marker>(
+        &mut self,
+        stage: Stage,
+        system: impl IntoSystem<Marker>,
+    ) -> SystemConfig<'_> {
+        self.batches = None;
+        self.systems.push(SystemNode {
+            system: Box::new(system.into_system()),
+            stage,
+            before: vec![],
+            after: vec![],
+        });
+
+        SystemConfig {
+            node: self.systems.last_mut().unwrap(),
+        }
+    }
+
+    // Sorts and batches the systems, done automatically by `run` after systems are added
+    pub fn initialize(&mut self) -> Result<(), ScheduleError> {
+        if self.batches.is_some() {
+            return Ok(());
+        }
+
+        let mut batches = vec![];
+        for stage in Stage::ALL {
+            let sorted = self.sort_stage(stage)?;
+            batches.push(self.batch_stage(&sorted));
+        }
+
+        self.batches = Some(batches);
+        Ok(())
+    }
+
+    pub fn run(&mut self, world: &mut World) -> Result<(), ScheduleError> {
+        self.initialize()?;
+
+        let batches = self.batches.as_ref().unwrap();
+
+        let threads = match self.executor {
+            Executor::SingleThreaded => {
+                for stage in batches {
+                    for batch in stage {
+                        for &idx in batch {
+                            self.systems[idx].system.run(world);
+                        }
+                    }
+                    world.flush();
+                }
+
+                world.clear_trackers();
+                return Ok(());
+            }
+            Executor::MultiThreaded { threads } => threads,
+        };
+
+        let pool = self.thread_pool.get_or_insert_with(|| {
+            ThreadPoolBuilder::new()
+                .num_threads(threads.unwrap_or(0))
+                .thread_name(|i| format!("khzeb-system-{i}"))
+                .build()
+                .expect("Failed to spawn the system thread pool")
+        });
+
+        for stage in batches {
+            let shared: &World = world;
+
+            for batch in stage {
+                if let [idx] = batch[..] {
+                    // SAFETY: nothing else runs in the meantime
+                    unsafe { self.systems[idx].system.run_unsafe(shared) };
+                    continue;
+                }
+
+                let nodes = self
+                    .systems
+                    .iter_mut()
+                    .enumerate()
+                    .filter(|(i, _)| batch.contains(i))
+                    .map(|(_, node)| node);
+
+                pool.scope(|scope| {
+                    for node in nodes {
+                        // SAFETY: systems in a batch have compatible access
+                        scope.spawn(move |_| unsafe { node.system.run_unsafe(shared) });
+                    }
+                });
+            }
+
+            // Sync point for structural changes
+            world.flush();
+        }
+
+        world.clear_trackers();
+        Ok(())
+    }
+
+    // Whether the constraints put system `a` before system `b`
+    fn is_ordered(&self, a: usize, b: usize) -> bool {
+        let (a, b) = (&self.systems[a], &self.systems[b]);
+        a.before.contains(&b.system.name()) || b.after.contains(&a.system.name())
+    }
+
+    // Puts every system into the earliest batch after everything it is ordered after
+    // or conflicts with, so the result matches running them one by one
+    fn batch_stage(&self, sorted: &[usize]) -> Vec<Vec<usize>> {
+        let mut batch_of = vec![0; sorted.len()];
+        let mut batches: Vec<Vec<usize>> = vec![];
+
+        for (i, &system) in sorted.iter().enumerate() {
+            let access = self.systems[system].system.access();
+
+            let batch = (0..i)
+                .filter(|&j| {
+                    let other = sorted[j];
+                    self.is_ordered(other, system)
+                        || !access.is_compatible(self.systems[other].system.access())
+                })
+                .map(|j| batch_of[j] + 1)
+                .max()
+                .unwrap_or(0);
+
+            batch_of[i] = batch;
+            if batch == batches.len() {
+                batches.push(vec![]);
+            }
+            batches[batch].push(system);
+        }
+
+        batches
+    }
+
+    // Topologically sorts the systems of a stage, keeping insertion order between unrelated ones
+    fn sort_stage(&self, stage: Stage) -> Result<Vec<usize>, ScheduleError> {
+        let nodes: Vec<usize> = (0..self.systems.len())
+            .filter(|&i| self.systems[i].stage == stage)
+            .collect();
+        let names: Vec<Name> = nodes
+            .iter()
+            .map(|&i| self.systems[i].system.name())
+            .collect();
+
+        // Edges between positions in `nodes`, `a -> b` meaning `a` runs before `b`
+        let mut successors = vec![vec![]; nodes.len()];
+        let mut predecessors = vec![vec![]; nodes.len()];
+        for (a, &node) in nodes.iter().enumerate() {
+            let node = &self.systems[node];
+            for (b, name) in names.iter().enumerate() {
+                if node.before.contains(name) {
+                    successors[a].push(b);
+                    predecessors[b].push(a);
+                }
+                if node.after.contains(name) {
+                    successors[b].push(a);
+                    predecessors[a].push(b);
+                }
+            }
+        }
+
+        let mut in_degree: Vec<usize> = predecessors.iter().map(Vec::len).collect();
+        let mut sorted = vec![];
+        let mut done = vec![false; nodes.len()];
+
+        while sorted.len() < nodes.len() {
+            let Some(next) = (0..nodes.len()).find(|&i| !done[i] && in_degree[i] == 0) else {
+                return Err(ScheduleError::Cycle {
+                    stage,
+                    systems: find_cycle(&predecessors, &done)
+                        .into_iter()
+                        .map(|i| names[i].clone())
+                        .collect(),
+                });
+            };
+
+            done[next] = true;
+            sorted.push(nodes[next]);
+            for &succ in &successors[next] {
+                in_degree[succ] -= 1;
+            }
+        }
+
+        Ok(sorted)
+    }
+}
+
+// Walks predecessors among the unsorted nodes until one repeats, every such node has one
+fn find_cycle(predecessors: &[Vec<usize>], done: &[bool]) -> Vec<usize> {
+    let mut path = vec![];
+    let mut current = (0..done.len()).find(|&i| !done[i]).unwrap();
+
+    while !path.contains(&current) {
+        path.push(current);
+        current = *predecessors[current].iter().find(|&&p| !done[p]).unwrap();
+    }
+
+    let start = path.iter().position(|&i| i == current).unwrap();
+    let mut cycle = path.split_off(start);
+    // The walk went against the edges
+    cycle.reverse();
+    cycle
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Barrier;
+
+    use crate::world::{component::Component, query::Query};
+
+    use super::*;
+
+    struct Log(Vec<&'static str>);
+
+    struct Counter(u32);
+
+    impl Component for Log {
+        fn component_name() -> Name {
+            Name::new("Log")
+        }
+    }
+
+    impl Component for Counter {
+        fn component_name() -> Name {
+            Name::new("Counter")
+        }
+    }
+
+    fn push(query: &mut Query<&mut Log>, entry: &'static str) {
+        for (_, mut log) in query.iter_mut() {
+            log.0.push(entry);
+        }
+    }
+
+    fn input(mut query: Query<&mut Log>) {
+        push(&mut query, "input");
+    }
+
+    fn movement(mut query: Query<&mut Log>) {
+        push(&mut query, "movement");
+    }
+
+    fn physics(mut query: Query<&mut Log>) {
+        push(&mut query, "physics");
+    }
+
+    fn render(mut query: Query<&mut Log>) {
+        push(&mut query, "render");
+    }
+
+    #[test]
+    fn test_ordering() {
+        let mut world = World::new();
+        let entity = world.spawn();
+        world.add_component(entity, Log(vec![])).unwrap();
+
+        let mut schedule = Schedule::new();
+        schedule.add_system(Stage::Render, render);
+        schedule.add_system(Stage::Update, physics).after(movement);
+        schedule.add_system(Stage::Update, movement);
+        schedule.add_system(Stage::PreUpdate, input);
+
+        schedule.run(&mut world).unwrap();
+
+        assert_eq!(
+            world.get::<Log>(entity).unwrap().0,
+            vec!["input", "movement", "physics", "render"]
+        );
+    }
+
+    static BARRIER: Barrier = Barrier::new(2);
+
+    fn log_waiting(mut query: Query<&mut Log>) {
+        BARRIER.wait();
+        push(&mut query, "log");
+    }
+
+    fn count_waiting(mut query: Query<&mut Counter>) {
+        BARRIER.wait();
+        for (_, mut counter) in query.iter_mut() {
+            counter.0 += 1;
+        }
+    }
+
+    #[test]
+    fn test_parallel() {
+        let mut world = World::new();
+        let entity = world.spawn();
+        world.add_component(entity, Log(vec![])).unwrap();
+        world.add_component(entity, Counter(0)).unwrap();
+
+        // Would deadlock on the barrier unless both systems run at once
+        let mut schedule =
+            Schedule::new().with_executor(Executor::MultiThreaded { threads: Some(2) });
+        schedule.add_system(Stage::Update, log_waiting);
+        schedule.add_system(Stage::Update, count_waiting);
+        schedule.add_system(Stage::Update, movement);
+        schedule.add_system(Stage::Update, physics);
+
+        schedule.run(&mut world).unwrap();
+        schedule.run(&mut world).unwrap();
+
+        assert_eq!(world.get::<Counter>(entity).unwrap().0, 2);
+        assert_eq!(
+            world.get::<Log>(entity).unwrap().0,
+            vec!["log", "movement", "physics", "log", "movement", "physics"]
+        );
+    }
+
+    #[test]
+    fn test_single_threaded() {
+        let mut world = World::new();
+        let entity = world.spawn();
+        world.add_component(entity, Log(vec![])).unwrap();
+
+        let mut schedule = Schedule::new().with_executor(Executor::SingleThreaded);
+        schedule.add_system(Stage::Update, physics);
+        schedule.add_system(Stage::Update, movement).before(physics);
+
+        schedule.run(&mut world).unwrap();
+
+        assert_eq!(
+            world.get::<Log>(entity).unwrap().0,
+            vec!["movement", "physics"]
+        );
+    }
+
+    #[test]
+    fn test_cycle() {
+        let mut schedule = Schedule::new();
+        schedule.add_system(Stage::Update, input);
+        schedule
+            .add_system(Stage::Update, movement)
+            .after(physics)
+            .before(render);
+        schedule.add_system(Stage::Update, physics).after(render);
+        schedule.add_system(Stage::Update, render);
+
+        let Err(ScheduleError::Cycle { stage, systems }) = schedule.initialize() else {
+            panic!("The cycle went unnoticed");
+        };
+
+        assert_eq!(stage, Stage::Update);
+        assert_eq!(systems.len(), 3);
+        assert!(!systems.contains(&system_name(&input)));
+    }
+}
@@ -0,0 +1,390 @@
+use std::{
+    collections::{BTreeMap, HashSet},
+    error::Error,
+    fmt,
+};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::utils::Name;
+
+use super::{
+    entity::{Entity, EntityGen, EntityIdx},
+    error::WorldError,
+    registry::ComponentRegistry,
+    world::World,
+};
+
+// Bumped whenever the layout of a snapshot changes
+pub const SNAPSHOT_VERSION: u32 = 1;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum SnapshotError {
+    // The component has no registration to write it out or read it back with
+    Unregistered(Name),
+    // The column does not hold the type registered under its name
+    TypeMismatch(Name),
+    UnsupportedVersion(u32),
+    // The entity bookkeeping does not add up
+    Corrupt(String),
+    World(WorldError),
+    // Malformed text or binary data, or component data that does not fit the type
+    Format(String),
+}
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SnapshotError::Unregistered(name) => {
+                write!(f, "component {} is not registered", &**name)
+            }
+            SnapshotError::TypeMismatch(name) => {
+                write!(
+                    f,
+                    "component {} does not match its registered type",
+                    &**name
+                )
+            }
+            SnapshotError::UnsupportedVersion(version) => {
+                write!(f, "snapshot version {version} is not supported")
+            }
+            SnapshotError::Corrupt(reason) => write!(f, "corrupt snapshot: {reason}"),
+            SnapshotError::World(err) => err.fmt(f),
+            SnapshotError::Format(reason) => write!(f, "malformed snapshot: {reason}"),
+        }
+    }
+}
+
+impl Error for SnapshotError {}
+
+impl From<WorldError> for SnapshotError {
+    fn from(value: WorldError) -> Self {
+        SnapshotError::World(value)
+    }
+}
+
+impl From<serde_json::Error> for SnapshotError {
+    fn from(value: serde_json::Error) -> Self {
+        SnapshotError::Format(value.to_string())
+    }
+}
+
+impl From<rmp_serde::encode::Error> for SnapshotError {
+    fn from(value: rmp_serde::encode::Error) -> Self {
+        SnapshotError::Format(value.to_string())
+    }
+}
+
+impl From<rmp_serde::decode::Error> for SnapshotError {
+    fn from(value: rmp_serde::decode::Error) -> Self {
+        SnapshotError::Format(value.to_string())
+    }
+}
+
+// The components of a single entity
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct EntitySnapshot {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub labels: Vec<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub components: BTreeMap<String, Value>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SlotSnapshot {
+    pub generation: EntityGen,
+    // `None` for vacant slots
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub entity: Option<EntitySnapshot>,
+}
+
+// Persistent copy of a world, restoring it keeps every entity ID valid.
+// Change ticks, resources and queued commands are not part of it.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub version: u32,
+    // Indexed by entity index
+    pub slots: Vec<SlotSnapshot>,
+    pub free_list: Vec<EntityIdx>,
+}
+
+impl Snapshot {
+    pub fn capture(world: &World, registry: &ComponentRegistry) -> Result<Self, SnapshotError> {
+        let mut slots: Vec<_> = world
+            .entity_slots()
+            .map(|(generation, occupied)| SlotSnapshot {
+                generation,
+                entity: occupied.then(EntitySnapshot::default),
+            })
+            .collect();
+
+        for archetype in world.archetypes().iter() {
+            for name in archetype.names() {
+                let column = archetype.column(name).unwrap();
+                let is_label = column.as_slice::<Name>().is_some();
+                let registration = registry.get(name);
+
+                for (row, entity) in archetype.entities().iter().enumerate() {
+                    let snapshot = slots[entity.index() as usize]
+                        .entity
+                        .get_or_insert_with(Default::default);
+
+                    if is_label {
+                        snapshot.labels.push(name.to_string());
+                        continue;
+                    }
+
+                    let registration =
+                        registration.ok_or_else(|| SnapshotError::Unregistered(name.clone()))?;
+                    snapshot
+                        .components
+                        .insert(name.to_string(), registration.serialize(column, row)?);
+                }
+            }
+        }
+
+        for (name, set) in world.sparse_sets().iter() {
+            let registration = registry.get(name);
+
+            for (row, entity) in set.entities().iter().enumerate() {
+                let registration =
+                    registration.ok_or_else(|| SnapshotError::Unregistered(name.clone()))?;
+                let value = registration.serialize(set.column(), row)?;
+                slots[entity.index() as usize]
+                    .entity
+                    .get_or_insert_with(Default::default)
+                    .components
+                    .insert(name.to_string(), value);
+            }
+        }
+
+        for snapshot in slots.iter_mut().filter_map(|slot| slot.entity.as_mut()) {
+            snapshot.labels.sort();
+        }
+
+        Ok(Self {
+            version: SNAPSHOT_VERSION,
+            slots,
+            free_list: world.free_list().to_vec(),
+        })
+    }
+
+    pub fn restore(&self, registry: &ComponentRegistry) -> Result<World, SnapshotError> {
+        if self.version != SNAPSHOT_VERSION {
+            return Err(SnapshotError::UnsupportedVersion(self.version));
+        }
+
+        self.validate_free_list()?;
+
+        let mut world = World::from_entity_slots(
+            self.slots
+                .iter()
+                .map(|slot| (slot.generation, slot.entity.is_some())),
+            self.free_list.clone(),
+        );
+
+        for (entity, snapshot) in self.entities() {
+            for label in &snapshot.labels {
+                world.add_component(entity, Name::new(label))?;
+            }
+
+            for (name, value) in &snapshot.components {
+                let name = Name::new(name);
+                registry
+                    .get(&name)
+                    .ok_or(SnapshotError::Unregistered(name))?
+                    .insert(&mut world, entity, value.clone())?;
+            }
+        }
+
+        Ok(world)
+    }
+
+    // The entities alive at the time of the capture
+    pub fn entities(&self) -> impl Iterator<Item = (Entity, &EntitySnapshot)> {
+        self.slots.iter().enumerate().filter_map(|(index, slot)| {
+            let entity = Entity::new(slot.generation, index as EntityIdx);
+            slot.entity.as_ref().map(|snapshot| (entity, snapshot))
+        })
+    }
+
+    // Pretty-printed JSON, meant for fixtures and diffing by hand
+    pub fn to_text(&self) -> Result<String, SnapshotError> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    pub fn from_text(text: &str) -> Result<Self, SnapshotError> {
+        Ok(serde_json::from_str(text)?)
+    }
+
+    // MessagePack with named fields, so older snapshots stay readable as fields are added
+    pub fn to_binary(&self) -> Result<Vec<u8>, SnapshotError> {
+        Ok(rmp_serde::to_vec_named(self)?)
+    }
+
+    pub fn from_binary(bytes: &[u8]) -> Result<Self, SnapshotError> {
+        Ok(rmp_serde::from_slice(bytes)?)
+    }
+
+    // Every vacant slot has to be on the free list exactly once, and nothing else
+    fn validate_free_list(&self) -> Result<(), SnapshotError> {
+        let mut seen = HashSet::new();
+
+        for &index in &self.free_list {
+            let slot = self.slots.get(index as usize).ok_or_else(|| {
+                SnapshotError::Corrupt(format!("free index {index} is out of bounds"))
+            })?;
+
+            if slot.entity.is_some() {
+                return Err(SnapshotError::Corrupt(format!(
+                    "free index {index} is occupied"
+                )));
+            }
+
+            if !seen.insert(index) {
+                return Err(SnapshotError::Corrupt(format!(
+                    "free index {index} is listed twice"
+                )));
+            }
+        }
+
+        let vacant = self
+            .slots
+            .iter()
+            .filter(|slot| slot.entity.is_none())
+            .count();
+        if vacant != seen.len() {
+            return Err(SnapshotError::Corrupt(
+                "vacant slots are missing from the free list".to_string(),
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::Vec2;
+
+    use crate::world::{component::Component, transform::Transform};
+
+    use super::*;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Health {
+        current: i32,
+        max: i32,
+    }
+
+    impl Component for Health {
+        fn component_name() -> Name {
+            Name::new("Health")
+        }
+    }
+
+    struct Secret;
+
+    impl Component for Secret {
+        fn component_name() -> Name {
+            Name::new("Secret")
+        }
+    }
+
+    fn registry() -> ComponentRegistry {
+        let mut registry = ComponentRegistry::new();
+        registry.register::<Health>().unwrap();
+        registry
+    }
+
+    fn sample_world() -> (World, Entity, Entity) {
+        let mut world = World::new();
+
+        let root = world.spawn();
+        world.add_component(root, Name::new("Player")).unwrap();
+        world
+            .add_component(root, Transform::from_translation(Vec2::new(1., 2.)))
+            .unwrap();
+
+        let dead = world.spawn();
+        let child = world.spawn();
+        world
+            .add_component(
+                child,
+                Health {
+                    current: 3,
+                    max: 10,
+                },
+            )
+            .unwrap();
+        world.set_parent(child, root).unwrap();
+
+        world.despawn(dead).unwrap();
+        world.spawn();
+        let temporary = world.spawn();
+        world.despawn(temporary).unwrap();
+
+        (world, root, child)
+    }
+
+    fn assert_restored(world: &mut World, root: Entity, child: Entity) {
+        assert!(world.has_component(root, Name::new("Player")));
+        assert_eq!(
+            world.get::<Transform>(root).unwrap().translation,
+            Vec2::new(1., 2.)
+        );
+        assert_eq!(world.get::<Health>(child).unwrap().current, 3);
+        assert_eq!(world.parent(child), Some(root));
+        assert_eq!(world.children(root), &[child]);
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let (mut original, root, child) = sample_world();
+        let registry = registry();
+
+        let snapshot = Snapshot::capture(&original, &registry).unwrap();
+
+        let text = snapshot.to_text().unwrap();
+        assert_eq!(Snapshot::from_text(&text).unwrap(), snapshot);
+
+        let binary = snapshot.to_binary().unwrap();
+        assert_eq!(Snapshot::from_binary(&binary).unwrap(), snapshot);
+
+        let mut restored = snapshot.restore(&registry).unwrap();
+        assert_restored(&mut restored, root, child);
+        assert_eq!(restored.free_list(), original.free_list());
+
+        // Both worlds hand out the same IDs from here on
+        assert_eq!(restored.spawn(), original.spawn());
+        assert_eq!(restored.spawn(), original.spawn());
+
+        let recaptured = Snapshot::capture(&restored, &registry).unwrap();
+        assert_eq!(recaptured, Snapshot::capture(&original, &registry).unwrap());
+    }
+
+    #[test]
+    fn test_errors() {
+        let (mut world, root, _) = sample_world();
+        let registry = registry();
+
+        let mut snapshot = Snapshot::capture(&world, &registry).unwrap();
+        snapshot.free_list.push(root.index());
+        assert!(matches!(
+            snapshot.restore(&registry),
+            Err(SnapshotError::Corrupt(_))
+        ));
+
+        assert!(matches!(
+            Snapshot::from_text("{ \"version\": 1 }"),
+            Err(SnapshotError::Format(_))
+        ));
+
+        world.add_component(root, Secret).unwrap();
+        assert_eq!(
+            Snapshot::capture(&world, &registry),
+            Err(SnapshotError::Unregistered(Name::new("Secret")))
+        );
+    }
+}
@@ -0,0 +1,241 @@
+use std::collections::HashMap;
+
+use crate::utils::Name;
+
+use super::{
+    archetype::{ArchetypeRow, Column},
+    change::{ComponentTicks, Tick},
+    entity::Entity,
+};
+
+// Storage of a single component outside of archetypes.
+// Adding and removing is cheap since the entity stays where it is, at the cost of a lookup per access.
+pub struct SparseSet {
+    // Parallel to `column`
+    entities: Vec<Entity>,
+    column: Column,
+    // The row in `column` by entity index
+    rows: Vec<Option<ArchetypeRow>>,
+}
+
+impl SparseSet {
+    pub fn new(column: Column) -> Self {
+        Self {
+            entities: vec![],
+            column,
+            rows: vec![],
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entities.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entities.is_empty()
+    }
+
+    pub fn entities(&self) -> &[Entity] {
+        &self.entities
+    }
+
+    pub fn column(&self) -> &Column {
+        &self.column
+    }
+
+    pub fn contains(&self, entity: Entity) -> bool {
+        self.row(entity).is_some()
+    }
+
+    // Where the component of the entity sits in `column`
+    pub fn row(&self, entity: Entity) -> Option<ArchetypeRow> {
+        self.rows
+            .get(entity.index() as usize)
+            .copied()
+            .flatten()
+            .filter(|&row| self.entities[row] == entity)
+    }
+
+    pub fn get_mut<T: 'static>(&mut self, entity: Entity) -> Option<(&mut T, &mut ComponentTicks)> {
+        let row = self.row(entity)?;
+        self.column.get_mut::<T>(row)
+    }
+
+    // The entity must not be in the set yet
+    pub(crate) fn insert<T: 'static>(
+        &mut self,
+        entity: Entity,
+        value: T,
+        tick: Tick,
+    ) -> Option<()> {
+        self.column.push(value, tick)?;
+
+        let index = entity.index() as usize;
+        if self.rows.len() <= index {
+            self.rows.resize(index + 1, None);
+        }
+        self.rows[index] = Some(self.entities.len());
+        self.entities.push(entity);
+        Some(())
+    }
+
+    // Moves the entity out of the column, which `take` has to remove the row from.
+    // Returns whether the entity was in the set.
+    pub(crate) fn remove_with(
+        &mut self,
+        entity: Entity,
+        take: impl FnOnce(ArchetypeRow, &mut Column),
+    ) -> bool {
+        let Some(row) = self.row(entity) else {
+            return false;
+        };
+
+        take(row, &mut self.column);
+        self.entities.swap_remove(row);
+        self.rows[entity.index() as usize] = None;
+
+        if let Some(moved) = self.entities.get(row) {
+            self.rows[moved.index() as usize] = Some(row);
+        }
+        true
+    }
+}
+
+// The sparse sets of every component stored in them, by name
+#[derive(Default)]
+pub struct SparseSets {
+    sets: HashMap<Name, SparseSet>,
+}
+
+impl SparseSets {
+    pub fn get(&self, name: &Name) -> Option<&SparseSet> {
+        self.sets.get(name)
+    }
+
+    pub(crate) fn get_mut(&mut self, name: &Name) -> Option<&mut SparseSet> {
+        self.sets.get_mut(name)
+    }
+
+    pub(crate) fn get_or_insert(
+        &mut self,
+        name: &Name,
+        column: impl FnOnce() -> Column,
+    ) -> &mut SparseSet {
+        self.sets
+            .entry(name.clone())
+            .or_insert_with(|| SparseSet::new(column()))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&Name, &SparseSet)> {
+        self.sets.iter()
+    }
+
+    // The names of the sets holding a component of the entity
+    pub(crate) fn names_of(&self, entity: Entity) -> Vec<Name> {
+        self.sets
+            .iter()
+            .filter(|(_, set)| set.contains(entity))
+            .map(|(name, _)| name.clone())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    use crate::world::{
+        component::{Component, StorageType},
+        query::{With, Without},
+        transform::Transform,
+        world::World,
+    };
+
+    use super::*;
+
+    #[derive(Component, Debug, PartialEq)]
+    #[component(storage = "sparse")]
+    struct Stunned(u32);
+
+    #[test]
+    fn test_sparse_components() {
+        let mut world = World::new();
+        assert_eq!(Stunned::storage_type(), StorageType::SparseSet);
+
+        let a = world
+            .spawn_bundle((Transform::IDENTITY, Stunned(2)))
+            .unwrap();
+        let b = world.spawn_bundle(Transform::IDENTITY).unwrap();
+        let c = world.spawn_bundle(Stunned(1)).unwrap();
+
+        // Toggling the component leaves the entity where it is
+        let archetype = world.location(b).unwrap().archetype;
+        world.add_component(b, Stunned(3)).unwrap();
+        assert_eq!(world.location(b).unwrap().archetype, archetype);
+        assert_eq!(world.location(a).unwrap().archetype, archetype);
+        assert_eq!(world.remove::<Stunned>(b), Ok(Stunned(3)));
+        assert_eq!(world.location(b).unwrap().archetype, archetype);
+
+        world.get_mut::<Stunned>(a).unwrap().0 += 1;
+        assert_eq!(world.get::<Stunned>(a), Ok(&Stunned(3)));
+        assert!(!world.has_component(b, Stunned::component_name()));
+
+        let mut stunned: Vec<_> = world
+            .query_ref::<(Entity, &Stunned)>()
+            .map(|(_, (entity, stunned))| (entity, stunned.0))
+            .collect();
+        stunned.sort();
+        assert_eq!(stunned, vec![(a, 3), (c, 1)]);
+
+        let mixed: Vec<_> = world
+            .query_ref::<(Entity, &Transform, Option<&Stunned>)>()
+            .map(|(_, (entity, _, stunned))| (entity, stunned.map(|s| s.0)))
+            .collect();
+        assert_eq!(mixed, vec![(a, Some(3)), (b, None)]);
+
+        let with: Vec<_> = world
+            .query_ref_filtered::<Entity, (With<Transform>, With<Stunned>)>()
+            .map(|(entity, _)| entity)
+            .collect();
+        assert_eq!(with, vec![a]);
+        let without: Vec<_> = world
+            .query_ref_filtered::<Entity, Without<Stunned>>()
+            .map(|(entity, _)| entity)
+            .collect();
+        assert_eq!(without, vec![b]);
+
+        for (_, mut stunned) in world.query::<&mut Stunned>() {
+            stunned.0 -= 1;
+        }
+        assert_eq!(world.get::<Stunned>(c), Ok(&Stunned(0)));
+    }
+
+    #[test]
+    fn test_sparse_despawn() {
+        let mut world = World::new();
+        let removed = Arc::new(AtomicUsize::new(0));
+        let counter = removed.clone();
+        world.on_remove::<Stunned>(move |world, entity| {
+            assert!(world.get::<Stunned>(entity).is_ok());
+            counter.fetch_add(1, Ordering::Relaxed);
+        });
+
+        let a = world.spawn_bundle(Stunned(0)).unwrap();
+        let b = world.spawn_bundle(Stunned(1)).unwrap();
+        world.despawn(a).unwrap();
+        assert_eq!(removed.load(Ordering::Relaxed), 1);
+
+        let set = world.sparse_sets().get(&Stunned::component_name()).unwrap();
+        assert_eq!(set.entities(), &[b]);
+        assert_eq!(set.row(b), Some(0));
+
+        // The slot of `a` is reused without its component
+        let c = world.spawn();
+        assert_eq!(c.index(), a.index());
+        assert!(world.get::<Stunned>(c).is_err());
+        assert_eq!(world.get::<Stunned>(b), Ok(&Stunned(1)));
+    }
+}
@@ -0,0 +1,246 @@
+use std::{
+    any::type_name,
+    marker::PhantomData,
+    ops::{Deref, DerefMut},
+};
+
+use crate::utils::Name;
+
+use super::{
+    change::{Tick, Ticks},
+    query::{Access, Query, QueryFilter, WorldQuery},
+    world::World,
+};
+
+// Something a system can take as an argument, fetched from the world on every run.
+// `State` is kept by the system between runs.
+pub trait SystemParam {
+    type Item<'w>: SystemParam<State = Self::State>;
+    type State: Send + 'static;
+
+    fn init_state() -> Self::State;
+
+    fn update_access(access: &mut Access);
+
+    /// # Safety
+    ///
+    /// Nothing else may borrow what `update_access` reports while the item is alive.
+    unsafe fn fetch<'w>(
+        state: &'w mut Self::State,
+        world: &'w World,
+        ticks: Ticks,
+    ) -> Self::Item<'w>;
+}
+
+pub type SystemParamItem<'w, P> = <P as SystemParam>::Item<'w>;
+
+impl<Q: WorldQuery + 'static, F: QueryFilter + 'static> SystemParam for Query<'_, Q, F> {
+    type Item<'w> = Query<'w, Q, F>;
+    type State = ();
+
+    fn init_state() -> Self::State {}
+
+    fn update_access(access: &mut Access) {
+        let mut own = Access::new();
+        Q::update_access(&mut own);
+        F::update_access(&mut own);
+        access.merge(&own);
+    }
+
+    unsafe fn fetch<'w>(_: &'w mut (), world: &'w World, ticks: Ticks) -> Self::Item<'w> {
+        Query::new(world, ticks)
+    }
+}
+
+// Value owned by the system itself, persisted between its runs
+pub struct Local<'s, T>(&'s mut T);
+
+impl<T> Deref for Local<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        self.0
+    }
+}
+
+impl<T> DerefMut for Local<'_, T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.0
+    }
+}
+
+impl<T: Default + Send + 'static> SystemParam for Local<'_, T> {
+    type Item<'w> = Local<'w, T>;
+    type State = T;
+
+    fn init_state() -> Self::State {
+        T::default()
+    }
+
+    fn update_access(_: &mut Access) {}
+
+    unsafe fn fetch<'w>(state: &'w mut T, _: &'w World, _: Ticks) -> Self::Item<'w> {
+        Local(state)
+    }
+}
+
+pub trait System: Send + 'static {
+    fn name(&self) -> Name;
+
+    fn access(&self) -> &Access;
+
+    /// # Safety
+    ///
+    /// Nothing else may conflict with `access` while the system runs.
+    unsafe fn run_unsafe(&mut self, world: &World);
+
+    fn run(&mut self, world: &mut World) {
+        // SAFETY: the world is borrowed exclusively
+        unsafe { self.run_unsafe(world) }
+    }
+}
+
+pub trait IntoSystem<Marker> {
+    type System: System;
+
+    fn into_system(self) -> Self::System;
+}
+
+impl<S: System> IntoSystem<()> for S {
+    type System = S;
+
+    fn into_system(self) -> Self::System {
+        self
+    }
+}
+
+// A plain function whose arguments are all system parameters.
+// `Marker` is the signature of the function and disambiguates the implementations.
+pub trait SystemParamFunction<Marker>: Send + 'static {
+    type Param: SystemParam;
+
+    fn run(&mut self, param: SystemParamItem<'_, Self::Param>);
+}
+
+pub struct FunctionSystem<F: SystemParamFunction<Marker>, Marker> {
+    func: F,
+    name: Name,
+    access: Access,
+    state: <F::Param as SystemParam>::State,
+    // The tick of the previous run, changes after it are new to the system
+    last_run: Tick,
+    _marker: PhantomData<fn() -> Marker>,
+}
+
+impl<F, Marker> System for FunctionSystem<F, Marker>
+where
+    F: SystemParamFunction<Marker>,
+    Marker: 'static,
+{
+    fn name(&self) -> Name {
+        self.name.clone()
+    }
+
+    fn access(&self) -> &Access {
+        &self.access
+    }
+
+    unsafe fn run_unsafe(&mut self, world: &World) {
+        let ticks = Ticks {
+            last_run: self.last_run,
+            this_run: world.increment_change_tick(),
+        };
+
+        // The parameters were checked not to alias each other when the system was created
+        let param = F::Param::fetch(&mut self.state, world, ticks);
+        self.func.run(param);
+
+        self.last_run = ticks.this_run;
+    }
+}
+
+pub struct FunctionMarker;
+
+impl<F, Marker> IntoSystem<(FunctionMarker, Marker)> for F
+where
+    F: SystemParamFunction<Marker>,
+    Marker: 'static,
+{
+    type System = FunctionSystem<F, Marker>;
+
+    fn into_system(self) -> Self::System {
+        let mut access = Access::new();
+        F::Param::update_access(&mut access);
+
+        FunctionSystem {
+            func: self,
+            name: Name::from_static(type_name::<F>()),
+            access,
+            state: F::Param::init_state(),
+            last_run: 0,
+            _marker: PhantomData,
+        }
+    }
+}
+
+// The name a system is known by in a schedule
+pub fn system_name<Marker, S: IntoSystem<Marker>>(_: &S) -> Name {
+    Name::from_static(type_name::<S>())
+}
+
+macro_rules! impl_system_function {
+    ($($p:ident),*) => {
+        #[allow(non_snake_case, unused_variables, clippy::unused_unit)]
+        impl<$($p: SystemParam),*> SystemParam for ($($p,)*) {
+            type Item<'w> = ($($p::Item<'w>,)*);
+            type State = ($($p::State,)*);
+
+            fn init_state() -> Self::State {
+                ($($p::init_state(),)*)
+            }
+
+            fn update_access(access: &mut Access) {
+                $($p::update_access(access);)*
+            }
+
+            unsafe fn fetch<'w>(
+                state: &'w mut Self::State,
+                world: &'w World,
+                ticks: Ticks,
+            ) -> Self::Item<'w> {
+                let ($($p,)*) = state;
+                ($($p::fetch($p, world, ticks),)*)
+            }
+        }
+
+        #[allow(non_snake_case)]
+        impl<Func, $($p: SystemParam),*> SystemParamFunction<fn($($p),*)> for Func
+        where
+            Func: Send + 'static,
+            for<'a> &'a mut Func: FnMut($($p),*) + FnMut($(SystemParamItem<'_, $p>),*),
+        {
+            type Param = ($($p,)*);
+
+            fn run(&mut self, param: SystemParamItem<'_, Self::Param>) {
+                // Naming the argument types explicitly drives the inference to the right `FnMut`
+                #[allow(clippy::too_many_arguments)]
+                fn call_inner<$($p),*>(mut f: impl FnMut($($p),*), $($p: $p),*) {
+                    f($($p),*)
+                }
+
+                let ($($p,)*) = param;
+                call_inner(self, $($p),*)
+            }
+        }
+    };
+}
+
+impl_system_function!();
+impl_system_function!(A);
+impl_system_function!(A, B);
+impl_system_function!(A, B, C);
+impl_system_function!(A, B, C, D);
+impl_system_function!(A, B, C, D, E);
+impl_system_function!(A, B, C, D, E, F);
+impl_system_function!(A, B, C, D, E, F, G);
+impl_system_function!(A, B, C, D, E, F, G, H);
@@ -0,0 +1,182 @@
+use glam::{Affine2, Vec2};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    component::Component,
+    entity::Entity,
+    hierarchy::{Children, Parent},
+    query::Query,
+    reflect::Reflect,
+};
+
+// Placement of an entity relative to its parent, or to the world for roots
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize, Component, Reflect)]
+pub struct Transform {
+    pub translation: Vec2,
+    // Counter-clockwise, in radians
+    pub rotation: f32,
+    pub scale: Vec2,
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+impl Transform {
+    pub const IDENTITY: Self = Self {
+        translation: Vec2::ZERO,
+        rotation: 0.,
+        scale: Vec2::ONE,
+    };
+
+    pub fn from_translation(translation: Vec2) -> Self {
+        Self {
+            translation,
+            ..Self::IDENTITY
+        }
+    }
+
+    pub fn compute_affine(&self) -> Affine2 {
+        Affine2::from_scale_angle_translation(self.scale, self.rotation, self.translation)
+    }
+}
+
+// Placement of an entity in the world, written by `propagate_transforms`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize, Component)]
+pub struct GlobalTransform(pub Affine2);
+
+impl GlobalTransform {
+    pub fn translation(&self) -> Vec2 {
+        self.0.translation
+    }
+
+    pub fn transform_point(&self, point: Vec2) -> Vec2 {
+        self.0.transform_point2(point)
+    }
+}
+
+type TransformQuery<'w> = Query<
+    'w,
+    (
+        &'static Transform,
+        &'static mut GlobalTransform,
+        Option<&'static Children>,
+        Option<&'static Parent>,
+    ),
+>;
+
+// Composes the transforms down the hierarchy, starting from the roots.
+// Descendants of an entity without a transform are left untouched.
+pub fn propagate_transforms(mut query: TransformQuery) {
+    // Each entity with the transform of its parent, kept on the heap for deep hierarchies
+    let mut stack: Vec<(Entity, Affine2)> = query
+        .iter_mut()
+        .filter(|(_, (_, _, _, parent))| parent.is_none())
+        .map(|(entity, _)| (entity, Affine2::IDENTITY))
+        .collect();
+
+    while let Some((entity, parent)) = stack.pop() {
+        let Ok((transform, mut global, children, _)) = query.get_mut(entity) else {
+            continue;
+        };
+
+        let affine = parent * transform.compute_affine();
+        // Unchanged globals are not written so `Changed<GlobalTransform>` stays meaningful
+        if global.0 != affine {
+            global.0 = affine;
+        }
+
+        if let Some(children) = children {
+            stack.extend(children.as_slice().iter().map(|&child| (child, affine)));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::FRAC_PI_2;
+
+    use crate::world::{
+        change::Changed,
+        schedule::{Schedule, Stage},
+        world::World,
+    };
+
+    use super::*;
+
+    fn spawn_at(world: &mut World, translation: Vec2) -> Entity {
+        let entity = world.spawn();
+        world
+            .add_component(entity, Transform::from_translation(translation))
+            .unwrap();
+        world
+            .add_component(entity, GlobalTransform::default())
+            .unwrap();
+        entity
+    }
+
+    #[test]
+    fn test_propagation() {
+        let mut world = World::new();
+
+        let root = spawn_at(&mut world, Vec2::new(10., 0.));
+        let child = spawn_at(&mut world, Vec2::new(1., 0.));
+        let grandchild = spawn_at(&mut world, Vec2::new(0., 1.));
+        world.set_parent(child, root).unwrap();
+        world.set_parent(grandchild, child).unwrap();
+
+        let mut schedule = Schedule::new();
+        schedule.add_system(Stage::PostUpdate, propagate_transforms);
+        schedule.run(&mut world).unwrap();
+
+        let global = |world: &World, e| world.get::<GlobalTransform>(e).unwrap().translation();
+        assert_eq!(global(&world, root), Vec2::new(10., 0.));
+        assert_eq!(global(&world, child), Vec2::new(11., 0.));
+        assert_eq!(global(&world, grandchild), Vec2::new(11., 1.));
+
+        // Rotating the root swings the whole subtree around it
+        world.get_mut::<Transform>(root).unwrap().rotation = FRAC_PI_2;
+        schedule.run(&mut world).unwrap();
+
+        assert!(global(&world, child).abs_diff_eq(Vec2::new(10., 1.), 1e-5));
+        assert!(global(&world, grandchild).abs_diff_eq(Vec2::new(9., 1.), 1e-5));
+
+        // Reparenting picks up the new parent on the next pass
+        world.remove_parent(grandchild).unwrap();
+        schedule.run(&mut world).unwrap();
+        assert_eq!(global(&world, grandchild), Vec2::new(0., 1.));
+
+        schedule.run(&mut world).unwrap();
+        assert_eq!(
+            world
+                .query_filtered::<Entity, Changed<GlobalTransform>>()
+                .count(),
+            0
+        );
+    }
+
+    #[test]
+    fn test_deep_hierarchy() {
+        let mut world = World::new();
+        let leaf = spawn_at(&mut world, Vec2::X);
+
+        // Grown upwards so attaching doesn't walk the whole chain
+        let mut root = leaf;
+        for _ in 1..100_000 {
+            let parent = spawn_at(&mut world, Vec2::X);
+            world.set_parent(root, parent).unwrap();
+            root = parent;
+        }
+
+        let mut schedule = Schedule::new();
+        schedule.add_system(Stage::PostUpdate, propagate_transforms);
+        schedule.run(&mut world).unwrap();
+
+        assert_eq!(
+            world.get::<GlobalTransform>(leaf).unwrap().translation(),
+            Vec2::new(100_000., 0.)
+        );
+    }
+}
@@ -1,66 +1,698 @@
-use std::any::Any;
-
-use micromap::Map;
+use std::{
+    any::type_name,
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU32, AtomicU64, Ordering},
+        Mutex,
+    },
+};
 
 use crate::utils::Name;
 
-use super::{component::Component, entity::Entity};
+use super::{
+    archetype::{ArchetypeId, ArchetypeRow, Archetypes, Column, EMPTY_ARCHETYPE},
+    bundle::Bundle,
+    change::{Mut, Tick, Ticks},
+    commands::{CommandQueue, Commands},
+    component::{register_component_type, Component, StorageType},
+    entity::{Entity, EntityGen, EntityIdx},
+    error::{WorldError, WorldResult},
+    observer::{ComponentEvent, Observers},
+    query::{QueryFilter, QueryIter, ReadOnlyWorldQuery, WorldQuery},
+    resource::Resources,
+    sparse_set::{SparseSet, SparseSets},
+};
+
+// Where the components of an entity live
+#[derive(Clone, Copy)]
+pub(crate) struct EntityLocation {
+    pub(crate) archetype: ArchetypeId,
+    pub(crate) row: ArchetypeRow,
+}
+
+struct EntitySlot {
+    generation: EntityGen,
+    // `None` for vacant slots
+    location: Option<EntityLocation>,
+}
 
 pub struct World {
     // The list of the slots for entities and their generations
-    // TODO(ktnlvr): Use an archetype-based component system
-    entity_list: Vec<(u32, Map<Name, Box<dyn Any>, 8>)>,
+    entity_list: Vec<EntitySlot>,
     // The list of vacant IDs
-    free_list: Vec<u32>,
+    free_list: Vec<EntityIdx>,
+    // The amount of IDs handed out through `&World` but not yet materialized.
+    // They come from the end of `free_list` first, then from past the end of `entity_list`.
+    reserved: AtomicU32,
+    archetypes: Archetypes,
+    sparse_sets: SparseSets,
+    resources: Resources,
+    observers: Observers,
+
+    commands: Mutex<CommandQueue>,
+
+    // The tick changes made right now are stamped with
+    change_tick: AtomicU64,
+    // The tick of the last `clear_trackers`, queries on the world look for changes after it
+    last_change_tick: Tick,
+    // Entities that lost a component and when, by the name of the component
+    removed: HashMap<Name, Vec<(Entity, Tick)>>,
 }
 
-impl World {
-    pub fn new() -> Self {
+impl Default for World {
+    fn default() -> Self {
         Self {
             entity_list: vec![],
             free_list: vec![],
+            reserved: Default::default(),
+            archetypes: Default::default(),
+            sparse_sets: Default::default(),
+            resources: Default::default(),
+            observers: Default::default(),
+            commands: Default::default(),
+            // Systems start out with a last run of 0, so everything is new to them
+            change_tick: AtomicU64::new(1),
+            last_change_tick: 0,
+            removed: Default::default(),
         }
     }
+}
+
+impl World {
+    pub fn new() -> Self {
+        Self::default()
+    }
 
     pub fn spawn(&mut self) -> Entity {
-        match self.free_list.pop() {
+        let entity = self.alloc_entity();
+        self.materialize(entity);
+        entity
+    }
+
+    // Takes a slot for a new entity, which the caller has to place in an archetype
+    pub(crate) fn alloc_entity(&mut self) -> Entity {
+        self.flush_reserved();
+
+        let (generation, index) = match self.free_list.pop() {
             Some(vacant_idx) => {
-                let (generation, _) = self.entity_list[vacant_idx as usize].0.overflowing_add(1);
-                self.entity_list[vacant_idx as usize] = (generation, Default::default());
-                Entity::new(generation, vacant_idx)
+                let slot = &mut self.entity_list[vacant_idx as usize];
+                let (generation, _) = slot.generation.overflowing_add(1);
+                slot.generation = generation;
+                (generation, vacant_idx)
             }
             None => {
-                let last_idx = self.entity_list.len() as u32;
-                self.entity_list.push((0, Default::default()));
-                Entity::new(0, last_idx)
+                let last_idx = self.entity_list.len() as EntityIdx;
+                self.entity_list.push(EntitySlot {
+                    generation: 0,
+                    location: None,
+                });
+                (0, last_idx)
             }
+        };
+
+        Entity::new(generation, index)
+    }
+
+    // Hands out an entity that starts existing at the next `flush`, from any thread
+    pub fn reserve_entity(&self) -> Entity {
+        let nth = self.reserved.fetch_add(1, Ordering::Relaxed);
+        self.reserved_entity(nth)
+    }
+
+    // Hands out `count` entities at once, they start existing at the next `flush`
+    pub fn reserve_entities(&self, count: u32) -> impl Iterator<Item = Entity> + '_ {
+        let first = self.reserved.fetch_add(count, Ordering::Relaxed);
+        (first..first + count).map(|nth| self.reserved_entity(nth))
+    }
+
+    // The entity of the `nth` reservation since the last flush.
+    // Vacant slots are reused the way `spawn` would, the most recently freed one first.
+    fn reserved_entity(&self, nth: u32) -> Entity {
+        let free = self.free_list.len();
+
+        match free.checked_sub(nth as usize + 1) {
+            Some(i) => {
+                let index = self.free_list[i];
+                let (generation, _) = self.entity_list[index as usize]
+                    .generation
+                    .overflowing_add(1);
+                Entity::new(generation, index)
+            }
+            None => Entity::new(
+                0,
+                (self.entity_list.len() + nth as usize - free) as EntityIdx,
+            ),
         }
     }
 
-    pub fn add_component<C: Component + 'static>(&mut self, to: Entity, component: C) {
-        let index = to.index() as usize;
+    pub fn commands(&self) -> Commands<'_> {
+        Commands::new(self)
+    }
+
+    pub(crate) fn queue_commands(&self, queue: &mut CommandQueue) {
+        self.commands.lock().unwrap().append(queue);
+    }
+
+    // Materializes reserved entities and applies the queued commands
+    pub fn flush(&mut self) {
+        self.flush_reserved();
+
+        let mut queue = std::mem::take(self.commands.get_mut().unwrap());
+        queue.apply(self);
+    }
 
-        let Some((_, components)) = self.entity_list.get_mut(index) else {
+    fn flush_reserved(&mut self) {
+        let reserved = *self.reserved.get_mut();
+        if reserved == 0 {
             return;
-        };
+        }
+
+        let entities: Vec<_> = (0..reserved).map(|nth| self.reserved_entity(nth)).collect();
+        *self.reserved.get_mut() = 0;
+
+        let reused = self.free_list.len().min(reserved as usize);
+        self.free_list.truncate(self.free_list.len() - reused);
 
-        components.insert(component.name(), Box::new(component));
+        for entity in entities {
+            if entity.index() as usize == self.entity_list.len() {
+                self.entity_list.push(EntitySlot {
+                    generation: 0,
+                    location: None,
+                });
+            }
+
+            self.entity_list[entity.index() as usize].generation = entity.generation();
+            self.materialize(entity);
+        }
     }
 
-    pub fn has_component(&self, entt: Entity, name: Name) -> bool {
+    // Places a freshly allocated entity without components
+    fn materialize(&mut self, entity: Entity) {
+        let row = self
+            .archetypes
+            .get_mut(EMPTY_ARCHETYPE)
+            .unwrap()
+            .push_entity(entity);
+
+        self.entity_list[entity.index() as usize].location = Some(EntityLocation {
+            archetype: EMPTY_ARCHETYPE,
+            row,
+        });
+    }
+
+    // Places a freshly allocated entity in the archetype, which has to hold exactly the table
+    // components of the bundle
+    pub(crate) fn place_bundle<B: Bundle>(
+        &mut self,
+        entity: Entity,
+        archetype: ArchetypeId,
+        bundle: B,
+    ) -> WorldResult<()> {
+        let tick = self.change_tick();
+        let target = self.archetypes.get_mut(archetype).unwrap();
+
+        bundle.push(entity, target, &mut self.sparse_sets, tick)?;
+        let row = target.push_entity(entity);
+
+        self.entity_list[entity.index() as usize].location =
+            Some(EntityLocation { archetype, row });
+        Ok(())
+    }
+
+    // Rebuilds the entity bookkeeping from the generation of every slot and whether it is occupied.
+    // Occupied slots start out without components.
+    pub(crate) fn from_entity_slots(
+        slots: impl IntoIterator<Item = (EntityGen, bool)>,
+        free_list: Vec<EntityIdx>,
+    ) -> Self {
+        let mut world = Self::new();
+
+        for (index, (generation, occupied)) in slots.into_iter().enumerate() {
+            world.entity_list.push(EntitySlot {
+                generation,
+                location: None,
+            });
+
+            if occupied {
+                world.materialize(Entity::new(generation, index as EntityIdx));
+            }
+        }
+
+        world.free_list = free_list;
+        world
+    }
+
+    // Places an entity without components at exactly the given index and generation.
+    // The slot must be vacant, skipped slots are left vacant with generation 0.
+    pub(crate) fn spawn_at(&mut self, entity: Entity) -> WorldResult<()> {
+        self.flush_reserved();
+        self.set_vacant_generation(entity.index(), entity.generation())?;
+
+        self.free_list.retain(|&index| index != entity.index());
+        self.materialize(entity);
+        Ok(())
+    }
+
+    // Sets the generation of a vacant slot, growing the list of slots if needed
+    pub(crate) fn set_vacant_generation(
+        &mut self,
+        index: EntityIdx,
+        generation: EntityGen,
+    ) -> WorldResult<()> {
+        self.flush_reserved();
+
+        while self.entity_list.len() <= index as usize {
+            self.entity_list.push(EntitySlot {
+                generation: 0,
+                location: None,
+            });
+        }
+
+        let slot = &mut self.entity_list[index as usize];
+        if slot.location.is_some() {
+            return Err(WorldError::InvalidEntity(Entity::new(generation, index)));
+        }
+
+        slot.generation = generation;
+        Ok(())
+    }
+
+    pub(crate) fn set_free_list(&mut self, free_list: Vec<EntityIdx>) {
+        self.flush_reserved();
+        self.free_list = free_list;
+    }
+
+    // The generation of every slot and whether it is occupied, by index
+    pub(crate) fn entity_slots(&self) -> impl Iterator<Item = (EntityGen, bool)> + '_ {
         self.entity_list
-            .get(entt.index() as usize)
-            .filter(|(_, cs)| cs.keys().find(|k| **k == name).is_some())
+            .iter()
+            .map(|slot| (slot.generation, slot.location.is_some()))
+    }
+
+    // Vacant indices in the order they are going to be reused, last first
+    pub fn free_list(&self) -> &[EntityIdx] {
+        &self.free_list
+    }
+
+    pub fn is_alive(&self, entity: Entity) -> bool {
+        self.location(entity).is_ok()
+    }
+
+    pub fn add_component<C: Component>(&mut self, to: Entity, component: C) -> WorldResult<()> {
+        if C::storage_type() == StorageType::SparseSet {
+            return self.add_sparse_component(to, component);
+        }
+
+        let location = self.location(to)?;
+
+        let name = component.name();
+        register_component_type::<C>(&name)?;
+        let tick = self.change_tick();
+        let source = self.archetypes.get_mut(location.archetype).unwrap();
+
+        // Already present, overwrite in place
+        if let Some(column) = source.column_mut(&name) {
+            let (slot, ticks) = column
+                .get_mut::<C>(location.row)
+                .ok_or_else(|| WorldError::TypeMismatch(name.clone(), type_name::<C>()))?;
+            *slot = component;
+            ticks.changed = tick;
+
+            self.trigger(ComponentEvent::Insert, to, &name);
+            return Ok(());
+        }
+
+        let target = self
+            .archetypes
+            .with_component::<C>(location.archetype, &name);
+
+        // Another type might have created the archetype under the same name
+        let column = self.archetypes.get(target).unwrap().column(&name).unwrap();
+        if !column.is::<C>() {
+            return Err(WorldError::TypeMismatch(name, type_name::<C>()));
+        }
+        let (source, target_archetype) = self.archetypes.pair_mut(location.archetype, target);
+
+        let (row, moved) = source.move_row(location.row, target_archetype, |_, _| {
+            unreachable!("Adding a component never drops another")
+        });
+        target_archetype.push_component(&name, component, tick);
+
+        self.relocate(moved, location);
+        self.entity_list[to.index() as usize].location = Some(EntityLocation {
+            archetype: target,
+            row,
+        });
+
+        self.trigger(ComponentEvent::Add, to, &name);
+        self.trigger(ComponentEvent::Insert, to, &name);
+        Ok(())
+    }
+
+    // The entity stays in its archetype
+    fn add_sparse_component<C: Component>(&mut self, to: Entity, component: C) -> WorldResult<()> {
+        self.location(to)?;
+
+        let name = component.name();
+        register_component_type::<C>(&name)?;
+        let tick = self.change_tick();
+        let set = self.sparse_sets.get_or_insert(&name, Column::new::<C>);
+        if !set.column().is::<C>() {
+            return Err(WorldError::TypeMismatch(name, type_name::<C>()));
+        }
+
+        match set.get_mut::<C>(to) {
+            Some((slot, ticks)) => {
+                *slot = component;
+                ticks.changed = tick;
+            }
+            None => {
+                set.insert(to, component, tick);
+                self.trigger(ComponentEvent::Add, to, &name);
+            }
+        }
+
+        self.trigger(ComponentEvent::Insert, to, &name);
+        Ok(())
+    }
+
+    pub fn has_component(&self, entt: Entity, name: Name) -> bool {
+        if let Some(set) = self.sparse_sets.get(&name) {
+            return set.contains(entt);
+        }
+
+        self.location(entt)
+            .ok()
+            .and_then(|location| self.archetypes.get(location.archetype))
+            .filter(|archetype| archetype.has(&name))
             .is_some()
     }
 
-    pub fn despawn(&mut self, entity: Entity) {
+    pub fn get<C: Component>(&self, entity: Entity) -> WorldResult<&C> {
+        let location = self.location(entity)?;
+        let name = C::component_name();
+
+        let (column, row) = match C::storage_type() {
+            StorageType::Table => (
+                self.archetypes
+                    .get(location.archetype)
+                    .and_then(|archetype| archetype.column(&name)),
+                Some(location.row),
+            ),
+            StorageType::SparseSet => {
+                let set = self.sparse_sets.get(&name);
+                (
+                    set.map(SparseSet::column),
+                    set.and_then(|set| set.row(entity)),
+                )
+            }
+        };
+
+        column
+            .and_then(|column| column.as_slice::<C>())
+            .zip(row)
+            .map(|(slice, row)| &slice[row])
+            .ok_or(WorldError::MissingComponent(entity, name))
+    }
+
+    pub fn get_mut<C: Component>(&mut self, entity: Entity) -> WorldResult<Mut<'_, C>> {
+        let location = self.location(entity)?;
+        let name = C::component_name();
+        let tick = self.change_tick();
+
+        let component = match C::storage_type() {
+            StorageType::Table => self
+                .archetypes
+                .get_mut(location.archetype)
+                .and_then(|archetype| archetype.column_mut(&name))
+                .and_then(|column| column.get_mut::<C>(location.row)),
+            StorageType::SparseSet => self
+                .sparse_sets
+                .get_mut(&name)
+                .and_then(|set| set.get_mut::<C>(entity)),
+        };
+
+        component
+            .map(|(value, ticks)| Mut::new(value, ticks, tick))
+            .ok_or(WorldError::MissingComponent(entity, name))
+    }
+
+    pub fn remove<C: Component>(&mut self, entity: Entity) -> WorldResult<C> {
+        let name = C::component_name();
+        let location = self.location(entity)?;
+
+        // Checked up front, a mismatch leaves the entity and the hooks alone
+        let column = match self.sparse_sets.get(&name) {
+            Some(set) => Some(set.column()),
+            None => self
+                .archetypes
+                .get(location.archetype)
+                .and_then(|archetype| archetype.column(&name)),
+        };
+        if column.is_some_and(|column| !column.is::<C>()) {
+            return Err(WorldError::TypeMismatch(name, type_name::<C>()));
+        }
+
+        let mut removed = None;
+        self.remove_with(entity, &name, |row, column| {
+            removed = column.swap_remove_take::<C>(row);
+        })?;
+
+        removed.ok_or_else(|| WorldError::TypeMismatch(name, type_name::<C>()))
+    }
+
+    // Drops the component stored under the name, whatever its type
+    pub fn remove_by_name(&mut self, entity: Entity, name: &Name) -> WorldResult<()> {
+        self.remove_with(entity, name, |row, column| column.swap_remove(row))
+    }
+
+    // Moves the entity out of the column, which `take` has to remove the row from
+    fn remove_with(
+        &mut self,
+        entity: Entity,
+        name: &Name,
+        mut take: impl FnMut(ArchetypeRow, &mut Column),
+    ) -> WorldResult<()> {
+        self.location(entity)?;
+        if !self.has_component(entity, name.clone()) {
+            return Err(WorldError::MissingComponent(entity, name.clone()));
+        }
+
+        // Hooks see the component before it goes, and might move the entity meanwhile
+        self.trigger(ComponentEvent::Remove, entity, name);
+        let location = self.location(entity)?;
+
+        if let Some(set) = self.sparse_sets.get_mut(name) {
+            if !set.remove_with(entity, take) {
+                return Err(WorldError::MissingComponent(entity, name.clone()));
+            }
+        } else {
+            let target = self.archetypes.without_component(location.archetype, name);
+            if target == location.archetype {
+                return Err(WorldError::MissingComponent(entity, name.clone()));
+            }
+
+            let (source, target_archetype) = self.archetypes.pair_mut(location.archetype, target);
+
+            let (row, moved) = source.move_row(location.row, target_archetype, |_, column| {
+                take(location.row, column);
+            });
+
+            self.relocate(moved, location);
+            self.entity_list[entity.index() as usize].location = Some(EntityLocation {
+                archetype: target,
+                row,
+            });
+        }
+
+        let tick = self.change_tick();
+        self.removed
+            .entry(name.clone())
+            .or_default()
+            .push((entity, tick));
+
+        Ok(())
+    }
+
+    pub fn despawn(&mut self, entity: Entity) -> WorldResult<()> {
+        let mut stack = self.despawn_single(entity, true)?;
+        while let Some(current) = stack.pop() {
+            // Entities reachable through several relations are already gone
+            if let Ok(cascade) = self.despawn_single(current, true) {
+                stack.extend(cascade);
+            }
+        }
+
+        Ok(())
+    }
+
+    // Despawns the entity without touching the hierarchy and relations around it,
+    // for replaying changes that already include what happened to the neighbours
+    pub(crate) fn despawn_unlinked(&mut self, entity: Entity) -> WorldResult<()> {
+        self.despawn_single(entity, false).map(|_| ())
+    }
+
+    // Despawns the entity alone, returning the ones its relations take down with it
+    fn despawn_single(&mut self, entity: Entity, linked: bool) -> WorldResult<Vec<Entity>> {
+        // Reservations depend on the free list staying as it is
+        self.flush_reserved();
+        let location = self.location(entity)?;
+
+        if !self.observers.is_empty() {
+            let mut names = self
+                .archetypes
+                .get(location.archetype)
+                .unwrap()
+                .names()
+                .to_vec();
+            names.extend(self.sparse_sets.names_of(entity));
+            for name in &names {
+                self.trigger(ComponentEvent::Remove, entity, name);
+            }
+            self.observers.forget(entity);
+        }
+
+        if linked {
+            self.unlink_hierarchy(entity);
+        }
+
+        // The hooks might have moved the entity
+        let location = self.location(entity)?;
+        let tick = self.change_tick();
+
+        let archetype = self.archetypes.get_mut(location.archetype).unwrap();
+        for name in archetype.names() {
+            self.removed
+                .entry(name.clone())
+                .or_default()
+                .push((entity, tick));
+        }
+
+        let moved = archetype.swap_remove(location.row);
+
+        for name in self.sparse_sets.names_of(entity) {
+            let set = self.sparse_sets.get_mut(&name).unwrap();
+            set.remove_with(entity, |row, column| column.swap_remove(row));
+            self.removed.entry(name).or_default().push((entity, tick));
+        }
+
+        self.relocate(moved, location);
+        self.entity_list[entity.index() as usize].location = None;
         self.free_list.push(entity.index());
+
+        if !linked {
+            return Ok(vec![]);
+        }
+
+        // Relations with the entity are cut, possibly taking other entities down with it
+        Ok(self.cleanup_relations(entity))
+    }
+
+    pub fn query<Q: WorldQuery>(&mut self) -> QueryIter<'_, Q, ()> {
+        self.query_filtered()
+    }
+
+    pub fn query_filtered<Q: WorldQuery, F: QueryFilter>(&mut self) -> QueryIter<'_, Q, F> {
+        // SAFETY: the world is borrowed exclusively for the lifetime of the iterator
+        unsafe { QueryIter::new(self, self.ticks()) }
+    }
+
+    pub fn query_ref<Q: ReadOnlyWorldQuery>(&self) -> QueryIter<'_, Q, ()> {
+        self.query_ref_filtered()
+    }
+
+    pub fn query_ref_filtered<Q: ReadOnlyWorldQuery, F: QueryFilter>(&self) -> QueryIter<'_, Q, F> {
+        // SAFETY: the query only reads and the world is not mutable while borrowed
+        unsafe { QueryIter::new(self, self.ticks()) }
+    }
+
+    pub fn change_tick(&self) -> Tick {
+        self.change_tick.load(Ordering::Relaxed)
+    }
+
+    // Hands out the current tick for a system run and moves on to the next one
+    pub(crate) fn increment_change_tick(&self) -> Tick {
+        self.change_tick.fetch_add(1, Ordering::Relaxed)
+    }
+
+    // Marks the end of a frame, queries on the world only see changes made after it.
+    // Removals and events stay around for one more frame for systems that ran before them.
+    pub fn clear_trackers(&mut self) {
+        self.update_events();
+
+        let previous = self.last_change_tick;
+        for removed in self.removed.values_mut() {
+            removed.retain(|(_, tick)| *tick > previous);
+        }
+
+        self.last_change_tick = self.increment_change_tick();
+    }
+
+    pub(crate) fn removed(&self, name: &Name) -> &[(Entity, Tick)] {
+        self.removed
+            .get(name)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    fn ticks(&self) -> Ticks {
+        Ticks {
+            last_run: self.last_change_tick,
+            this_run: self.change_tick(),
+        }
+    }
+
+    pub fn archetypes(&self) -> &Archetypes {
+        &self.archetypes
+    }
+
+    pub fn sparse_sets(&self) -> &SparseSets {
+        &self.sparse_sets
+    }
+
+    pub(crate) fn storages_mut(&mut self) -> (&mut Archetypes, &mut SparseSets) {
+        (&mut self.archetypes, &mut self.sparse_sets)
+    }
+
+    pub fn resources(&self) -> &Resources {
+        &self.resources
+    }
+
+    pub(crate) fn resources_mut(&mut self) -> &mut Resources {
+        &mut self.resources
+    }
+
+    pub(crate) fn observers(&self) -> &Observers {
+        &self.observers
+    }
+
+    pub(crate) fn observers_mut(&mut self) -> &mut Observers {
+        &mut self.observers
+    }
+
+    pub(crate) fn location(&self, entity: Entity) -> WorldResult<EntityLocation> {
+        let slot = self
+            .entity_list
+            .get(entity.index() as usize)
+            .ok_or(WorldError::InvalidEntity(entity))?;
+
+        slot.location
+            .filter(|_| slot.generation == entity.generation())
+            .ok_or(WorldError::StaleEntity(entity))
+    }
+
+    // Points an entity swapped into a vacated row to its new place
+    fn relocate(&mut self, moved: Option<Entity>, location: EntityLocation) {
+        if let Some(moved) = moved {
+            self.entity_list[moved.index() as usize].location = Some(location);
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use crate::world::component::NameCollision;
+
     use super::*;
 
     #[test]
@@ -70,7 +702,7 @@ mod tests {
         let a = world.spawn();
         let (generation, index) = a.decouple();
 
-        world.despawn(a);
+        world.despawn(a).unwrap();
 
         let b = world.spawn();
         let (next_generation, next_index) = b.decouple();
@@ -90,8 +722,8 @@ mod tests {
         let a = world.spawn();
         let b = world.spawn();
 
-        world.add_component(a, label_a.clone());
-        world.add_component(b, label_b.clone());
+        world.add_component(a, label_a.clone()).unwrap();
+        world.add_component(b, label_b.clone()).unwrap();
 
         assert!(world.has_component(a, label_a.clone()));
         assert!(world.has_component(b, label_b.clone()));
@@ -99,10 +731,167 @@ mod tests {
         assert!(!world.has_component(a, label_b));
         assert!(!world.has_component(b, label_a));
 
-        world.add_component(a, label_shared.clone());
-        world.add_component(b, label_shared.clone());
+        world.add_component(a, label_shared.clone()).unwrap();
+        world.add_component(b, label_shared.clone()).unwrap();
 
         assert!(world.has_component(a, label_shared.clone()));
         assert!(world.has_component(b, label_shared));
     }
+
+    #[test]
+    pub fn test_archetype_moves() {
+        let mut world = World::new();
+
+        let label_a = Name::new("A");
+        let label_b = Name::new("B");
+
+        let entities: Vec<_> = (0..16).map(|_| world.spawn()).collect();
+        for (i, &e) in entities.iter().enumerate() {
+            world.add_component(e, label_a.clone()).unwrap();
+            if i % 2 == 0 {
+                world.add_component(e, label_b.clone()).unwrap();
+            }
+        }
+
+        world.despawn(entities[0]).unwrap();
+        world.despawn(entities[3]).unwrap();
+
+        for (i, &e) in entities.iter().enumerate().skip(1).filter(|(i, _)| *i != 3) {
+            assert!(world.has_component(e, label_a.clone()));
+            assert_eq!(world.has_component(e, label_b.clone()), i % 2 == 0);
+        }
+
+        assert!(!world.has_component(entities[0], label_a));
+        assert!(!world.has_component(entities[3], label_b));
+    }
+
+    #[test]
+    pub fn test_get_remove() {
+        let mut world = World::new();
+
+        let a = world.spawn();
+        world.add_component(a, Name::new("A")).unwrap();
+        world.add_component(a, ()).unwrap();
+
+        assert_eq!(world.get::<()>(a), Ok(&()));
+        assert_eq!(world.remove::<()>(a), Ok(()));
+        assert_eq!(
+            world.get::<()>(a),
+            Err(WorldError::MissingComponent(a, Name::new("()")))
+        );
+        assert!(world.remove::<()>(a).is_err());
+        assert!(world.has_component(a, Name::new("A")));
+    }
+
+    #[test]
+    pub fn test_type_mismatch() {
+        // Claims the name of the unit component by hand
+        struct Impostor;
+
+        impl Component for Impostor {
+            fn component_name() -> Name {
+                Name::new("()")
+            }
+        }
+
+        let mut world = World::new();
+        let a = world.spawn();
+        let b = world.spawn();
+        world.add_component(a, ()).unwrap();
+
+        let collision = Err(WorldError::NameCollision(NameCollision {
+            name: Name::new("()"),
+            existing: type_name::<()>(),
+            colliding: type_name::<Impostor>(),
+        }));
+        assert_eq!(world.add_component(a, Impostor), collision);
+        assert_eq!(world.add_component(b, Impostor), collision);
+        assert_eq!(
+            world.remove::<Impostor>(a).map(|_| ()),
+            Err(WorldError::TypeMismatch(
+                Name::new("()"),
+                type_name::<Impostor>()
+            ))
+        );
+        assert_eq!(world.get::<()>(a), Ok(&()));
+        assert_eq!(
+            world.get::<()>(b),
+            Err(WorldError::MissingComponent(b, Name::new("()")))
+        );
+    }
+
+    #[test]
+    pub fn test_double_despawn() {
+        let mut world = World::new();
+
+        let a = world.spawn();
+        world.despawn(a).unwrap();
+        assert_eq!(world.despawn(a), Err(WorldError::StaleEntity(a)));
+
+        // A corrupted free list would hand out the same slot twice
+        let b = world.spawn();
+        let c = world.spawn();
+        assert_ne!(b.index(), c.index());
+    }
+
+    #[test]
+    pub fn test_use_after_despawn() {
+        let mut world = World::new();
+
+        let a = world.spawn();
+        world.add_component(a, ()).unwrap();
+        world.despawn(a).unwrap();
+
+        assert!(!world.is_alive(a));
+        assert_eq!(world.get::<()>(a), Err(WorldError::StaleEntity(a)));
+
+        let b = world.spawn();
+        assert_eq!(a.index(), b.index());
+        assert!(world.is_alive(b));
+        assert!(!world.is_alive(a));
+
+        assert_eq!(world.add_component(a, ()), Err(WorldError::StaleEntity(a)));
+        assert!(world.get::<()>(b).is_err());
+        assert_eq!(
+            world.despawn(Entity::new(0, 42)),
+            Err(WorldError::InvalidEntity(Entity::new(0, 42)))
+        );
+    }
+
+    #[test]
+    pub fn test_reserve() {
+        let mut world = World::new();
+        let [a, b, c] = [world.spawn(), world.spawn(), world.spawn()];
+        world.despawn(b).unwrap();
+        world.despawn(a).unwrap();
+
+        let reserved: Vec<_> = std::thread::scope(|scope| {
+            let world = &world;
+            let threads: Vec<_> = (0..4)
+                .map(|_| scope.spawn(move || world.reserve_entities(8).collect::<Vec<_>>()))
+                .collect();
+
+            let mut reserved = vec![world.reserve_entity()];
+            for thread in threads {
+                reserved.extend(thread.join().unwrap());
+            }
+            reserved
+        });
+
+        let mut indices: Vec<_> = reserved.iter().map(|e| e.index()).collect();
+        indices.sort();
+        indices.dedup();
+        assert_eq!(indices.len(), 33);
+        assert!(reserved.iter().all(|&e| !world.is_alive(e)));
+
+        world.flush();
+        assert!(reserved.iter().all(|&e| world.is_alive(e)));
+        assert!(world.is_alive(c));
+        // The freed slots are reused with their next generation
+        assert!(reserved.contains(&Entity::new(1, a.index())));
+        assert!(reserved.contains(&Entity::new(1, b.index())));
+
+        assert!(world.free_list().is_empty());
+        assert_eq!(world.spawn(), Entity::new(0, 34));
+    }
 }
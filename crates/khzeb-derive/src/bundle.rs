@@ -0,0 +1,55 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{Data, DeriveInput, Error, Index, Member, Result};
+
+pub fn derive(input: DeriveInput) -> Result<TokenStream> {
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let fields = match &input.data {
+        Data::Struct(data) => &data.fields,
+        _ => {
+            return Err(Error::new_spanned(
+                &input.ident,
+                "Bundle can only be derived for structs",
+            ))
+        }
+    };
+
+    let members: Vec<_> = fields
+        .iter()
+        .enumerate()
+        .map(|(i, field)| match &field.ident {
+            Some(ident) => Member::Named(ident.clone()),
+            None => Member::Unnamed(Index::from(i)),
+        })
+        .collect();
+
+    Ok(quote! {
+        impl #impl_generics ::khzeb::world::bundle::Bundle for #ident #ty_generics #where_clause {
+            fn components(
+                &self,
+                components: &mut ::std::vec::Vec<::khzeb::world::bundle::BundleComponent>,
+            ) {
+                #(::khzeb::world::bundle::Bundle::components(&self.#members, components);)*
+            }
+
+            fn push(
+                self,
+                entity: ::khzeb::world::entity::Entity,
+                archetype: &mut ::khzeb::world::archetype::Archetype,
+                sparse_sets: &mut ::khzeb::world::sparse_set::SparseSets,
+                tick: ::khzeb::world::change::Tick,
+            ) -> ::khzeb::world::error::WorldResult<()> {
+                #(::khzeb::world::bundle::Bundle::push(
+                    self.#members,
+                    entity,
+                    archetype,
+                    sparse_sets,
+                    tick,
+                )?;)*
+                ::std::result::Result::Ok(())
+            }
+        }
+    })
+}
@@ -0,0 +1,82 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{DeriveInput, Error, LitStr, Result};
+
+#[derive(Default)]
+struct Attributes {
+    // Given with `#[component(name = "...")]`
+    name: Option<LitStr>,
+    // Given with `#[component(storage = "table")]` or `#[component(storage = "sparse")]`
+    storage: Option<TokenStream>,
+}
+
+fn attributes(input: &DeriveInput) -> Result<Attributes> {
+    let mut attributes = Attributes::default();
+
+    for attr in input
+        .attrs
+        .iter()
+        .filter(|a| a.path().is_ident("component"))
+    {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("name") {
+                attributes.name = Some(meta.value()?.parse()?);
+                Ok(())
+            } else if meta.path.is_ident("storage") {
+                let storage: LitStr = meta.value()?.parse()?;
+                attributes.storage = Some(match storage.value().as_str() {
+                    "table" => quote!(::khzeb::world::component::StorageType::Table),
+                    "sparse" => quote!(::khzeb::world::component::StorageType::SparseSet),
+                    _ => return Err(Error::new_spanned(storage, "expected `table` or `sparse`")),
+                });
+                Ok(())
+            } else {
+                Err(meta.error("expected `name` or `storage`"))
+            }
+        })?;
+    }
+
+    Ok(attributes)
+}
+
+pub fn derive(input: DeriveInput) -> Result<TokenStream> {
+    let ident = &input.ident;
+
+    // Every instantiation would need a name of its own
+    if !input.generics.params.is_empty() {
+        return Err(Error::new_spanned(
+            &input.generics,
+            "Component can't be derived for generic types",
+        ));
+    }
+
+    let attributes = attributes(&input)?;
+
+    let name = match attributes.name {
+        Some(name) => quote!(#name),
+        None => quote!(::std::concat!(
+            ::std::module_path!(),
+            "::",
+            ::std::stringify!(#ident)
+        )),
+    };
+
+    let storage = attributes.storage.map(|storage| {
+        quote! {
+            fn storage_type() -> ::khzeb::world::component::StorageType {
+                #storage
+            }
+        }
+    });
+
+    Ok(quote! {
+        impl ::khzeb::world::component::Component for #ident {
+            #storage
+
+            fn component_name() -> ::khzeb::utils::Name {
+                static NAME: ::khzeb::utils::StaticName = ::khzeb::utils::StaticName::new(#name);
+                NAME.get()
+            }
+        }
+    })
+}
@@ -0,0 +1,35 @@
+use proc_macro::TokenStream;
+use syn::{parse_macro_input, DeriveInput};
+
+mod bundle;
+mod component;
+mod reflect;
+
+// Every field has to be a bundle itself, its components are added in the order of the fields
+#[proc_macro_derive(Bundle)]
+pub fn derive_bundle(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    bundle::derive(input)
+        .unwrap_or_else(|err| err.to_compile_error())
+        .into()
+}
+
+// Fields marked `#[reflect(skip)]` are left out and don't need to implement `Reflect`
+#[proc_macro_derive(Reflect, attributes(reflect))]
+pub fn derive_reflect(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    reflect::derive(input)
+        .unwrap_or_else(|err| err.to_compile_error())
+        .into()
+}
+
+// Named after the module path and the type unless `#[component(name = "...")]` says otherwise.
+// Two types ending up with the same name are reported once the second one is stored or registered.
+// `#[component(storage = "sparse")]` keeps the component in a sparse set instead of archetypes.
+#[proc_macro_derive(Component, attributes(component))]
+pub fn derive_component(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    component::derive(input)
+        .unwrap_or_else(|err| err.to_compile_error())
+        .into()
+}
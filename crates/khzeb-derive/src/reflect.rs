@@ -0,0 +1,91 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{Data, DeriveInput, Error, Fields, Index, Member, Result};
+
+// Whether the field carries `#[reflect(skip)]`
+fn is_skipped(field: &syn::Field) -> Result<bool> {
+    let mut skipped = false;
+
+    for attr in field.attrs.iter().filter(|a| a.path().is_ident("reflect")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("skip") {
+                skipped = true;
+                Ok(())
+            } else {
+                Err(meta.error("expected `skip`"))
+            }
+        })?;
+    }
+
+    Ok(skipped)
+}
+
+pub fn derive(input: DeriveInput) -> Result<TokenStream> {
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let fields = match &input.data {
+        Data::Struct(data) => &data.fields,
+        _ => {
+            return Err(Error::new_spanned(
+                &input.ident,
+                "Reflect can only be derived for structs",
+            ))
+        }
+    };
+
+    let mut names = vec![];
+    let mut members = vec![];
+
+    for (i, field) in fields.iter().enumerate() {
+        if is_skipped(field)? {
+            continue;
+        }
+
+        // Tuple fields go by their position
+        let (name, member) = match (&field.ident, fields) {
+            (Some(ident), Fields::Named(_)) => (ident.to_string(), Member::Named(ident.clone())),
+            _ => (i.to_string(), Member::Unnamed(Index::from(i))),
+        };
+
+        names.push(name);
+        members.push(member);
+    }
+
+    Ok(quote! {
+        impl #impl_generics ::khzeb::world::reflect::Reflect for #ident #ty_generics #where_clause {
+            fn reflect_type_name(&self) -> &'static str {
+                ::std::any::type_name::<Self>()
+            }
+
+            fn field_names(&self) -> ::std::vec::Vec<::std::string::String> {
+                ::std::vec![#(::std::string::String::from(#names)),*]
+            }
+
+            fn field(&self, name: &str) -> ::std::option::Option<&dyn ::khzeb::world::reflect::Reflect> {
+                match name {
+                    #(#names => ::std::option::Option::Some(&self.#members),)*
+                    _ => ::std::option::Option::None,
+                }
+            }
+
+            fn field_mut(
+                &mut self,
+                name: &str,
+            ) -> ::std::option::Option<&mut dyn ::khzeb::world::reflect::Reflect> {
+                match name {
+                    #(#names => ::std::option::Option::Some(&mut self.#members),)*
+                    _ => ::std::option::Option::None,
+                }
+            }
+
+            fn as_any(&self) -> &dyn ::std::any::Any {
+                self
+            }
+
+            fn as_any_mut(&mut self) -> &mut dyn ::std::any::Any {
+                self
+            }
+        }
+    })
+}